@@ -0,0 +1,287 @@
+
+// Ticks a second for the rules that move, shrink, kill and grow the snakes, the same ones the game
+// runs every tick, on big boards, with long snakes and with lots of snakes at once, so changes to
+// the grid, the controller and extending snakes can be measured. Run with `cargo bench`.
+//
+// Each snake goes round and round a loop that fills a rectangle of the board, so it never runs into
+// anything and a board can be ticked for as long as it's measured. Nothing spawns, since food or
+// power-ups landing in a loop would end the game sooner or later. Once measured, every snake has to
+// still be going round its own loop, so a busy tick losing the events saying who died can't pass
+// for one where nobody did.
+
+#[macro_use]
+extern crate criterion;
+extern crate snake;
+extern crate specs;
+
+use criterion::{Criterion, ParameterizedBenchmark, Throughput};
+use specs::{Dispatcher, DispatcherBuilder, Entity, Join, World};
+
+use snake::components::{Direction, Extension, Parent, Player, Snake, Tick, Tile, Type};
+use snake::event::Events;
+use snake::grid::Grid;
+use snake::{simulation, tick};
+
+const SEED: u64 = 1;
+
+// How many ticks the growing benchmarks play, growing a piece each tick.
+const GROWTH: u64 = 100;
+
+// A rectangle of the board a snake loops around.
+#[derive(Clone, Copy, Debug)]
+struct Loop {
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+}
+impl Loop {
+    // A loop with room for a snake of a length, four rows high for short snakes and two hundred for
+    // long ones so even the longest fit on a 1000x1000 board.
+    fn holding(x: usize, y: usize, length: usize) -> Loop {
+        let height = if length < 200 { 4 } else { 200 };
+        let width = (length / height + 2) / 2 * 2;
+        Loop { x: x, y: y, width: width.max(2), height: height }
+    }
+
+    // Which way to go from a tile of the loop. Along the top row back to the left, then down and up
+    // the columns in turn back to the right.
+    fn way(&self, x: usize, y: usize) -> (i8, i8) {
+        let (column, row) = (x - self.x, y - self.y);
+        if row == 0 {
+            if column == 0 { (0, 1) } else { (-1, 0) }
+        }
+        else if column % 2 == 0 {
+            if row == self.height - 1 { (1, 0) } else { (0, 1) }
+        }
+        else if row == 1 {
+            if column == self.width - 1 { (0, -1) } else { (1, 0) }
+        }
+        else {
+            (0, -1)
+        }
+    }
+
+    // Every tile of the loop in the order it goes round, starting from the top left.
+    fn tiles(&self) -> Vec<(usize, usize)> {
+        let mut tiles = Vec::with_capacity(self.width * self.height);
+        let (mut x, mut y) = (self.x, self.y);
+        for _ in 0..self.width * self.height {
+            tiles.push((x, y));
+            let (way_x, way_y) = self.way(x, y);
+            x = (x as i32 + way_x as i32) as usize;
+            y = (y as i32 + way_y as i32) as usize;
+        }
+        tiles
+    }
+
+    fn contains(&self, tile: &Tile) -> bool {
+        let (x, y) = (tile.x as usize, tile.y as usize);
+        tile.x >= 0 && tile.y >= 0 && x >= self.x && y >= self.y
+            && x < self.x + self.width && y < self.y + self.height
+    }
+}
+
+// A board of snakes running the movement rules, steered round their loops.
+struct Board {
+    world: World,
+    dispatcher: Dispatcher<'static, 'static>,
+
+    // Which way to go from each tile, for whichever loop it's in.
+    ways: Vec<(i8, i8)>,
+    len: (usize, usize),
+
+    // The loop each player's snake goes round, in player order.
+    loops: Vec<Loop>,
+}
+impl Board {
+    fn new(x: usize, y: usize) -> Board {
+        let mut world = World::new();
+        simulation::register(&mut world, x, y, SEED);
+
+        let dispatcher = simulation::movement(DispatcherBuilder::new(), &[]).build();
+
+        Board {
+            world: world,
+            dispatcher: dispatcher,
+            ways: vec![(0, 0); x * y],
+            len: (x, y),
+            loops: Vec::new(),
+        }
+    }
+
+    // A board with a snake of a length in a loop of its own, growing by as much as it's given.
+    fn with_snake(x: usize, y: usize, length: usize, growth: u16) -> Board {
+        let mut board = Board::new(x, y);
+        let around = Loop::holding(0, 0, length + growth as usize + 1);
+        board.snake(0, around, length, growth);
+        board
+    }
+
+    // A board packed with as many loops of a snake of a length each as it takes.
+    fn with_snakes(count: usize, length: usize) -> Board {
+        let around = Loop::holding(0, 0, length + 1);
+        let across = (count as f64).sqrt().ceil() as usize;
+        let down = (count + across - 1) / across;
+        let mut board = Board::new(across * around.width, down * around.height);
+
+        for player in 0..count {
+            let (column, row) = (player % across, player / across);
+            let around = Loop { x: column * around.width, y: row * around.height, ..around };
+            board.snake(player, around, length, 0);
+        }
+        board
+    }
+
+    // Lay a snake out along the start of a loop, heading round it.
+    fn snake(&mut self, player: usize, around: Loop, length: usize, growth: u16) -> Entity {
+        let tiles = around.tiles();
+        assert!(length < tiles.len(), "a snake has to have room to move round its loop");
+        assert_eq!(player, self.loops.len(), "snakes are laid out in player order");
+        self.loops.push(around);
+        for &(x, y) in &tiles {
+            let index = x + y * self.len.0;
+            self.ways[index] = around.way(x, y);
+        }
+
+        // The head is furthest round the loop, with the tail trailing back to the start of it.
+        let mut segments: Vec<Entity> = Vec::with_capacity(length);
+        for &(x, y) in tiles[..length].iter().rev() {
+            let tile = Tile { x: x as i32, y: y as i32 };
+            let direction = Direction { direction: around.way(x, y), previous: None };
+            let mut builder = self.world.create_entity()
+                .with(tile)
+                .with(direction)
+                .with(Player(player))
+                .with(Type::Snake);
+            if let Some(&parent) = segments.last() {
+                builder = builder.with(Parent(parent));
+            }
+            let segment = builder.build();
+            self.world.write_resource::<Grid>().insert(x, y, segment, Type::Snake);
+            segments.push(segment);
+        }
+
+        let head = segments[0];
+        let (x, y) = tiles[length - 1];
+        self.world.write::<Snake>().insert(head, Snake {
+            queued_direction: around.way(x, y),
+            length: length as u32,
+            score: 0,
+            end: if length > 1 { segments.last().cloned() } else { None },
+        });
+        if growth > 0 {
+            self.world.write::<Extension>().insert(head, Extension(growth));
+        }
+        head
+    }
+
+    // Steer every snake on round its loop and run the rules for a tick.
+    fn tick(&mut self) {
+        {
+            let tiles = self.world.read::<Tile>();
+            let mut snakes = self.world.write::<Snake>();
+            for (snake, tile) in (&mut snakes, &tiles).join() {
+                snake.queued_direction = self.ways[tile.x as usize + tile.y as usize * self.len.0];
+            }
+
+            tick::next(&mut self.world.write_resource::<Tick>(), &mut self.world.write_resource::<Events>());
+        }
+
+        self.dispatcher.dispatch(&mut self.world.res);
+        self.world.maintain();
+    }
+
+    fn game_over(&self) -> bool {
+        self.world.read_resource::<Tick>().game_over
+    }
+
+    // Whether every snake is still on the board with its head somewhere round its own loop.
+    fn on_loops(&self) -> bool {
+        let snakes = self.world.read::<Snake>();
+        let players = self.world.read::<Player>();
+        let tiles = self.world.read::<Tile>();
+
+        let heads = (&snakes, &players, &tiles).join()
+            .filter(|&(_, player, tile)| self.loops[player.0].contains(tile))
+            .count();
+        heads == self.loops.len()
+    }
+}
+
+// Run a board for a tick, making sure the snakes are still going.
+fn tick(board: &mut Board) {
+    board.tick();
+    assert!(!board.game_over(), "a snake ran into something");
+}
+
+// Make sure a board measured for as long as it took still has every snake going round its loop.
+fn check(board: &Board) {
+    assert!(board.on_loops(), "a snake left its loop");
+}
+
+// One snake on boards from small to 1000x1000.
+fn boards(c: &mut Criterion) {
+    let benchmark = ParameterizedBenchmark::new(
+        "board",
+        |b, &size| {
+            let mut board = Board::with_snake(size, size, 20, 0);
+            b.iter(|| tick(&mut board));
+            check(&board);
+        },
+        vec![10, 100, 1000],
+    );
+    c.bench("ticks", benchmark.throughput(|_| Throughput::Elements(1)));
+}
+
+// One snake up to 100,000 pieces long on a 1000x1000 board.
+fn lengths(c: &mut Criterion) {
+    let benchmark = ParameterizedBenchmark::new(
+        "length",
+        |b, &length| {
+            let mut board = Board::with_snake(1000, 1000, length, 0);
+            b.iter(|| tick(&mut board));
+            check(&board);
+        },
+        vec![10, 1000, 10000, 100000],
+    );
+    c.bench("ticks", benchmark.throughput(|_| Throughput::Elements(1)));
+}
+
+// Lots of snakes of 20 pieces each, side by side in loops of their own.
+fn snakes(c: &mut Criterion) {
+    let benchmark = ParameterizedBenchmark::new(
+        "snakes",
+        |b, &count| {
+            let mut board = Board::with_snakes(count, 20);
+            b.iter(|| tick(&mut board));
+            check(&board);
+        },
+        vec![1, 10, 100, 1000, 10000],
+    );
+    c.bench("ticks", benchmark.throughput(|_| Throughput::Elements(1)));
+}
+
+// A snake growing a piece every tick, starting from up to 10,000 pieces long. Growing can't go on
+// forever, so each sample starts again from a new board that isn't counted in the time taken.
+fn growing(c: &mut Criterion) {
+    let benchmark = ParameterizedBenchmark::new(
+        "growing",
+        |b, &length| {
+            b.iter_with_setup(
+                || Board::with_snake(1000, 1000, length, GROWTH as u16),
+                |mut board| {
+                    for _ in 0..GROWTH {
+                        tick(&mut board);
+                    }
+                    check(&board);
+                },
+            )
+        },
+        vec![10, 1000, 10000],
+    );
+    c.bench("ticks", benchmark.sample_size(10).throughput(|_| Throughput::Elements(GROWTH as u32)));
+}
+
+criterion_group!(benches, boards, lengths, snakes, growing);
+criterion_main!(benches);
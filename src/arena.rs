@@ -0,0 +1,174 @@
+
+// An arena that closes in on the snakes, turning the outermost open ring of the board into walls
+// every so often. Closed rings are ordinary walls on the grid, so running into them, placing food
+// and moving live food all treat them like the edge of the board without knowing about the arena.
+
+use specs::{Entities, Entity, Fetch, FetchMut, Join, ReadStorage, System, WriteStorage};
+
+use body::Followers;
+use components::{Parent, Snake, Tick, Tile, Type};
+use event::{Cause, Events, GameEvent};
+use grid::{Grid, Layer};
+
+// How many ticks before a ring closes that the snakes are warned.
+pub const WARNING: u64 = 20;
+
+// The arena stops closing once it would be smaller than this across.
+pub const MIN_SIZE: usize = 4;
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Arena {
+    // How many ticks between rings closing, never if zero.
+    pub every: u64,
+
+    // How many rings have closed so far.
+    pub closed: usize,
+}
+impl Arena {
+    // Which ring of the board a position is in, counting in from the edge.
+    pub fn ring(x: usize, y: usize, len: (usize, usize)) -> usize {
+        x.min(y).min(len.0 - 1 - x).min(len.1 - 1 - y)
+    }
+
+    // Whether another ring can close without leaving the arena too small.
+    pub fn can_close(&self, len: (usize, usize)) -> bool {
+        let inside = 2 * (self.closed + 1);
+        len.0 >= inside + MIN_SIZE && len.1 >= inside + MIN_SIZE
+    }
+
+    // How many ticks until the next ring closes, if one will.
+    pub fn closing_in(&self, tick: &Tick, len: (usize, usize)) -> Option<u64> {
+        if self.every == 0 || !self.can_close(len) {
+            return None;
+        }
+
+        Some(self.every - tick.ticks % self.every)
+    }
+
+    // Positions of the next ring to close while the snakes are being warned about it.
+    pub fn closing(&self, tick: &Tick, len: (usize, usize)) -> Vec<(usize, usize)> {
+        match self.closing_in(tick, len) {
+            Some(ticks) if ticks <= WARNING => self.next_ring(len),
+            _ => Vec::new(),
+        }
+    }
+
+    fn next_ring(&self, len: (usize, usize)) -> Vec<(usize, usize)> {
+        let mut ring = Vec::new();
+        for x in 0..len.0 {
+            for y in 0..len.1 {
+                if Arena::ring(x, y, len) == self.closed {
+                    ring.push((x, y));
+                }
+            }
+        }
+        ring
+    }
+}
+
+// Warns the snakes before the arena closes in, then walls off the next ring. Food, power-ups and
+// terrain in the ring are crushed, snakes caught with their head in it die and snakes caught with
+// only their body in it lose everything from there to the end of their tail.
+#[derive(Default)]
+pub struct ArenaSystem;
+impl<'a> System<'a> for ArenaSystem {
+    type SystemData = (
+        // Resources
+        Entities<'a>,
+        Fetch<'a, Tick>,
+        FetchMut<'a, Arena>,
+        FetchMut<'a, Grid>,
+        FetchMut<'a, Events>,
+
+        // Components
+        WriteStorage<'a, Tile>,
+        WriteStorage<'a, Type>,
+        WriteStorage<'a, Snake>,
+        ReadStorage<'a, Parent>,
+    );
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, tick, mut arena, mut grid, mut events, mut tiles, mut types, mut snakes, parents) = data;
+
+        if !tick.ticked {
+            return;
+        }
+
+        let closing = match arena.closing_in(&tick, grid.len) {
+            Some(ticks) => ticks,
+            None => return,
+        };
+        if closing == WARNING {
+            events.publish(GameEvent::ArenaWarning { ring: arena.closed, ticks: WARNING });
+        }
+        // The count starts over at every once a ring has closed.
+        if closing != arena.every {
+            return;
+        }
+
+        let ring = arena.next_ring(grid.len);
+        arena.closed += 1;
+        info!("Arena closed in to ring {}", arena.closed);
+
+        // Snake segments caught in the ring, nearest the head first.
+        let followers = Followers::new(&entities, &parents);
+        for (head, snake) in (&*entities, &mut snakes).join() {
+            let body = followers.body(head, snake.end);
+
+            let caught = body.iter().position(|&segment| {
+                tiles.get(segment)
+                    .map(|tile| ring.contains(&(tile.x as usize, tile.y as usize)))
+                    .unwrap_or(false)
+            });
+            match caught {
+                Some(0) => {
+                    let tile = tiles.get(head).cloned().unwrap_or_default();
+                    events.publish(GameEvent::SnakeDied { who: head, cause: Cause::Crushed, tile: tile });
+                },
+                Some(index) => {
+                    for &segment in &body[index..] {
+                        crush(&entities, &mut grid, &tiles, segment);
+                    }
+                    snake.end = if index > 1 { Some(body[index - 1]) } else { None };
+                    snake.length -= (body.len() - index) as u32;
+                },
+                None => { },
+            }
+        }
+
+        for (x, y) in ring {
+            if let Some(item) = grid.get(Layer::Item, x, y) {
+                crush(&entities, &mut grid, &tiles, item.entity);
+            }
+
+            // Terrain other than walls is walled over. Portals take their other end with them so
+            // nothing can come out here.
+            if let Some(terrain) = grid.get(Layer::Terrain, x, y) {
+                if let Type::Portal { x: exit_x, y: exit_y } = terrain.kind {
+                    if let Some(exit) = grid.get(Layer::Terrain, exit_x, exit_y) {
+                        crush(&entities, &mut grid, &tiles, exit.entity);
+                    }
+                }
+                if terrain.kind != Type::Wall {
+                    crush(&entities, &mut grid, &tiles, terrain.entity);
+                }
+            }
+
+            if grid.get(Layer::Terrain, x, y).is_none() {
+                let wall = entities.create();
+                tiles.insert(wall, Tile { x: x as i32, y: y as i32 });
+                types.insert(wall, Type::Wall);
+                grid.insert(x, y, wall, Type::Wall);
+            }
+        }
+
+        events.publish(GameEvent::ArenaClosed { ring: arena.closed - 1 });
+    }
+}
+
+// Take something caught in a closing ring off the board.
+fn crush(entities: &Entities, grid: &mut Grid, tiles: &WriteStorage<Tile>, entity: Entity) {
+    if let Some(tile) = tiles.get(entity) {
+        grid.remove(tile.x as usize, tile.y as usize, entity);
+    }
+    entities.delete(entity);
+}
@@ -0,0 +1,51 @@
+
+// Hosts a networked game, waiting for a client to connect for each player before starting.
+//
+// Usage: server [address] [players] [stream target]
+//
+// Clients join with `terminal --connect <address>`.
+
+#[macro_use]
+extern crate log;
+extern crate snake;
+
+use std::env;
+use std::time::{Duration, Instant};
+use std::thread;
+
+use snake::net::Server;
+use snake::{logging, random};
+use snake::stream::Stream;
+use snake::{GRID_X, GRID_Y};
+
+fn main() {
+    logging::init();
+
+    let mut args = env::args().skip(1);
+    let address = args.next().unwrap_or_else(|| "0.0.0.0:7575".to_owned());
+    let players = args.next()
+        .and_then(|players| players.parse().ok())
+        .unwrap_or(2);
+    let stream = args.next();
+
+    info!("Waiting for {} players on {}", players, address);
+    let mut server = Server::host(&*address, players, (GRID_X, GRID_Y), random::seed())
+        .expect("failed to start the server");
+    info!("All players connected, starting");
+
+    if let Some(target) = stream {
+        server.simulation.stream(Stream::open(&target).expect("failed to open the stream"));
+    }
+
+    let mut last = Instant::now();
+    loop {
+        let now = Instant::now();
+        if let Err(error) = server.update(now - last) {
+            error!("Stopping: {}", error);
+            return;
+        }
+        last = now;
+
+        thread::sleep(Duration::from_millis(1));
+    }
+}
@@ -0,0 +1,67 @@
+
+// Watches a game streamed by another frontend with `--stream`, without being able to play.
+//
+// Usage: spectator <source>
+//
+// The source is `tcp:<address>` to connect to a game serving frames, `-` for standard input, or
+// the path of a file frames are being written to, which is followed as it grows.
+
+extern crate serde_json;
+extern crate snake;
+
+use std::env;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::net::TcpStream;
+use std::thread;
+use std::time::Duration;
+
+use snake::stream::Frame;
+use snake::terminal;
+
+fn main() {
+    let source = env::args().nth(1).expect("expected a source to watch");
+    let (reader, follow): (Box<Read>, bool) = if source.starts_with("tcp:") {
+        (Box::new(TcpStream::connect(&source[4..]).expect("failed to connect")), false)
+    }
+    else if source == "-" {
+        (Box::new(io::stdin()), false)
+    }
+    else {
+        (Box::new(File::open(&source).expect("failed to open the file")), true)
+    };
+
+    let mut reader = BufReader::new(reader);
+    let mut line = String::new();
+    loop {
+        match reader.read_line(&mut line) {
+            Ok(0) if follow => {
+                // Wait for the file to grow.
+                thread::sleep(Duration::from_millis(20));
+                continue;
+            },
+            Ok(0) => return,
+            Ok(_) if !line.ends_with('\n') => continue,
+            Ok(_) => { },
+            Err(error) => {
+                println!("Stopped watching: {}", error);
+                return;
+            },
+        }
+
+        match serde_json::from_str::<Frame>(&line) {
+            Ok(frame) => {
+                // Clear the screen and draw from the top left.
+                print!("\x1b[2J\x1b[H");
+                println!("{}", terminal::status(&frame));
+                for line in terminal::draw(&frame).into_iter().chain(terminal::scores(&frame)) {
+                    println!("{}", line);
+                }
+                println!("Tick {}{}", frame.tick, if frame.game_over { "  Game over!" } else { "" });
+            },
+            Err(error) => println!("Skipping a bad frame: {}", error),
+        }
+
+        line.clear();
+    }
+}
@@ -0,0 +1,270 @@
+
+// Plays the game in a terminal, for playing over SSH or on machines without a GPU.
+//
+// Usage: terminal [players] [--mode <name>] [--level <name>] [--stream <target>] [--telemetry <path>]
+//        terminal --connect <address> [--stream <target>] [--telemetry <path>]
+//
+// Player 1 uses WASD and player 2 uses the arrow keys (the arrow keys also steer player 1 when
+// playing alone). When connected to a server both steer this client's player. R restarts and Q or
+// Escape quits. F5 saves a local game and F9 loads it again, M switches a local game's mode.
+
+extern crate snake;
+#[cfg(unix)]
+extern crate termion;
+
+#[cfg(unix)]
+use std::io;
+#[cfg(unix)]
+use std::time::Duration;
+
+#[cfg(unix)]
+use snake::mode::GameMode;
+#[cfg(unix)]
+use snake::net::Client;
+#[cfg(unix)]
+use snake::simulation::Simulation;
+
+// Either a game played on this machine or one hosted by a server.
+#[cfg(unix)]
+enum Game {
+    Local(Simulation),
+    Remote(Client),
+}
+#[cfg(unix)]
+impl Game {
+    fn simulation(&self) -> &Simulation {
+        match *self {
+            Game::Local(ref simulation) => simulation,
+            Game::Remote(ref client) => &client.simulation,
+        }
+    }
+
+    fn simulation_mut(&mut self) -> &mut Simulation {
+        match *self {
+            Game::Local(ref mut simulation) => simulation,
+            Game::Remote(ref mut client) => &mut client.simulation,
+        }
+    }
+
+    // Which player the arrow keys steer.
+    fn arrows(&self) -> usize {
+        match *self {
+            Game::Local(ref simulation) if simulation.players > 1 => 1,
+            _ => 0,
+        }
+    }
+
+    fn steer(&mut self, player: usize, wanted: (i8, i8)) -> io::Result<()> {
+        match *self {
+            Game::Local(ref mut simulation) => Ok(simulation.steer(player, wanted)),
+            Game::Remote(ref mut client) => client.steer(wanted),
+        }
+    }
+
+    // Only local games can switch mode, a networked game plays the server's.
+    fn switch_mode(&mut self) -> io::Result<()> {
+        match *self {
+            Game::Local(ref mut simulation) => {
+                let mode = simulation.world.read_resource::<GameMode>().next();
+                Ok(simulation.mode(mode))
+            },
+            Game::Remote(_) => Err(io::Error::new(io::ErrorKind::Other, "networked games can't switch mode")),
+        }
+    }
+
+    fn restart(&mut self) -> io::Result<()> {
+        match *self {
+            Game::Local(ref mut simulation) => Ok(simulation.reset()),
+            Game::Remote(ref mut client) => client.restart(),
+        }
+    }
+
+    // Only local games can be saved, a networked game belongs to its server.
+    fn save(&self, key: &str) -> io::Result<()> {
+        match *self {
+            Game::Local(ref simulation) => simulation.save(key),
+            Game::Remote(_) => Err(io::Error::new(io::ErrorKind::Other, "networked games can't be saved")),
+        }
+    }
+
+    fn load(&mut self, key: &str) -> io::Result<()> {
+        match *self {
+            Game::Local(ref mut simulation) => simulation.load(key),
+            Game::Remote(_) => Err(io::Error::new(io::ErrorKind::Other, "networked games can't be loaded")),
+        }
+    }
+
+    // Returns whether anything changed since the last update.
+    fn update(&mut self, delta: Duration) -> io::Result<bool> {
+        match *self {
+            Game::Local(ref mut simulation) => Ok(simulation.update(delta)),
+            Game::Remote(ref mut client) => client.update().map(|applied| applied > 0),
+        }
+    }
+}
+
+#[cfg(unix)]
+fn main() {
+    use std::env;
+    use std::io::Write;
+    use std::thread;
+    use std::time::Instant;
+
+    use termion::event::Key;
+    use termion::input::TermRead;
+    use termion::raw::IntoRawMode;
+    use termion::{clear, cursor};
+
+    use snake::level::Level;
+    use snake::mode::Config;
+    use snake::stream::{Frame, Stream};
+    use snake::telemetry::TelemetrySystem;
+    use snake::terminal;
+    use snake::{GRID_X, GRID_Y, PLAYERS};
+
+    const QUICKSAVE: &'static str = "quicksave";
+
+    let mut players = PLAYERS;
+    let mut connect = None;
+    let mut stream = None;
+    let mut telemetry = None;
+    let mut level = None;
+    let mut mode = None;
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match &*arg {
+            "--connect" => connect = Some(args.next().expect("--connect needs the server's address")),
+            "--stream" => stream = Some(args.next().expect("--stream needs a target")),
+            "--telemetry" => telemetry = Some(args.next().expect("--telemetry needs a path")),
+            "--level" => level = Some(args.next().expect("--level needs a name")),
+            "--mode" => mode = Some(args.next().expect("--mode needs a name")),
+            _ => players = arg.parse().expect("expected the number of players"),
+        }
+    }
+
+    let mut game = match connect {
+        Some(address) => {
+            println!("Waiting for the other players to connect to {}", address);
+            Game::Remote(Client::connect(&*address).expect("failed to connect to the server"))
+        },
+        None => {
+            let mut simulation = Simulation::new(GRID_X, GRID_Y, players);
+            simulation.mode(Config::read().mode(mode).expect("failed to pick the game mode"));
+            if let Some(name) = level {
+                simulation.level(Level::named(&name).expect("failed to load the level"));
+            }
+            Game::Local(simulation)
+        },
+    };
+
+    if let Some(target) = stream {
+        game.simulation_mut().stream(Stream::open(&target).expect("failed to open the stream"));
+    }
+
+    if let Some(path) = telemetry {
+        game.simulation_mut().record(TelemetrySystem::open(&path).expect("failed to open the telemetry file"));
+    }
+
+    let mut keys = termion::async_stdin().keys();
+    let mut stdout = io::stdout().into_raw_mode().unwrap();
+    write!(stdout, "{}{}", clear::All, cursor::Hide).unwrap();
+
+    let arrows = game.arrows();
+    let mut status = String::new();
+    let mut redraw = true;
+    let mut last = Instant::now();
+    let result = loop {
+        let mut input = Ok(());
+        let mut quit = false;
+        while let Some(Ok(key)) = keys.next() {
+            input = match key {
+                Key::Char('q') | Key::Esc => {
+                    quit = true;
+                    Ok(())
+                },
+                Key::Char('r') => {
+                    redraw = true;
+                    game.restart()
+                },
+                Key::Char('m') => {
+                    status = match game.switch_mode() {
+                        Ok(()) => String::new(),
+                        Err(error) => format!("Failed to switch mode: {}", error),
+                    };
+                    redraw = true;
+                    Ok(())
+                },
+                Key::F(5) => {
+                    status = match game.save(QUICKSAVE) {
+                        Ok(()) => "Saved.".to_owned(),
+                        Err(error) => format!("Failed to save: {}", error),
+                    };
+                    redraw = true;
+                    Ok(())
+                },
+                Key::F(9) => {
+                    status = match game.load(QUICKSAVE) {
+                        Ok(()) => "Loaded.".to_owned(),
+                        Err(error) => format!("Failed to load: {}", error),
+                    };
+                    redraw = true;
+                    Ok(())
+                },
+                Key::Char('a') => game.steer(0, (-1, 0)),
+                Key::Char('d') => game.steer(0, (1, 0)),
+                Key::Char('w') => game.steer(0, (0, -1)),
+                Key::Char('s') => game.steer(0, (0, 1)),
+                Key::Left => game.steer(arrows, (-1, 0)),
+                Key::Right => game.steer(arrows, (1, 0)),
+                Key::Up => game.steer(arrows, (0, -1)),
+                Key::Down => game.steer(arrows, (0, 1)),
+                _ => Ok(()),
+            }.and(input);
+        }
+
+        if quit {
+            break Ok(());
+        }
+
+        let now = Instant::now();
+        match input.and_then(|_| game.update(now - last)) {
+            Ok(changed) => redraw |= changed,
+            Err(error) => break Err(error),
+        }
+        last = now;
+
+        if redraw {
+            let simulation = game.simulation();
+            write!(stdout, "{}", cursor::Goto(1, 1)).unwrap();
+            let frame = Frame::capture(&simulation.world);
+            write!(stdout, "{}{}\r\n", terminal::status(&frame), clear::UntilNewline).unwrap();
+            for line in terminal::draw(&frame) {
+                write!(stdout, "{}\r\n", line).unwrap();
+            }
+
+            if simulation.game_over() {
+                write!(stdout, "Game over! Press R to restart.{}\r\n", clear::UntilNewline).unwrap();
+            }
+            else {
+                write!(stdout, "{}\r\n", clear::CurrentLine).unwrap();
+            }
+            write!(stdout, "{}{}\r\n", status, clear::UntilNewline).unwrap();
+
+            stdout.flush().unwrap();
+            redraw = false;
+        }
+
+        thread::sleep(Duration::from_millis(5));
+    };
+
+    write!(stdout, "{}{}", clear::All, cursor::Show).unwrap();
+    if let Err(error) = result {
+        write!(stdout, "Disconnected: {}\r\n", error).unwrap();
+    }
+}
+
+#[cfg(not(unix))]
+fn main() {
+    println!("The terminal frontend is only supported on unix terminals.");
+}
@@ -0,0 +1,35 @@
+
+// Walking a snake from its head back along its tail. Parents only point towards the head, so the
+// segment behind each one is looked up once and shared by every snake walked in a system run.
+
+use std::collections::HashMap;
+
+use specs::{Entities, Entity, Join, ReadStorage};
+
+use components::Parent;
+
+pub struct Followers(HashMap<Entity, Entity>);
+impl Followers {
+    pub fn new(entities: &Entities, parents: &ReadStorage<Parent>) -> Followers {
+        Followers((&**entities, parents).join().map(|(entity, parent)| (parent.0, entity)).collect())
+    }
+
+    // The segment right behind another one, if anything follows it.
+    pub fn behind(&self, segment: Entity) -> Option<Entity> {
+        self.0.get(&segment).cloned()
+    }
+
+    // Every segment of a snake from its head back to `end`, the end of its tail. Segments behind the
+    // end were cut off this tick and are only waiting to be deleted, so they're left out.
+    pub fn body(&self, head: Entity, end: Option<Entity>) -> Vec<Entity> {
+        let end = end.unwrap_or(head);
+        let mut body = vec![head];
+        while *body.last().unwrap() != end && body.len() <= self.0.len() {
+            match self.behind(*body.last().unwrap()) {
+                Some(follower) => body.push(follower),
+                None => break,
+            }
+        }
+        body
+    }
+}
@@ -1,6 +1,8 @@
-use specs::{Component, Entity, DenseVecStorage};
+use specs::{Component, Entity, DenseVecStorage, NullStorage};
 use amethyst::ecs::resources::Button;
 
+use grid::Layer;
+
 #[derive(Debug, Default)]
 pub struct Tick {
     // How many ticks have happened so far
@@ -10,66 +12,12 @@ pub struct Tick {
     pub ticked: bool,
 
     pub game_over: bool,
-}
-
-// Grid of snake parts.
-pub struct Grid {
-    list: Vec<Option<Entity>>,
-    pub len: (usize, usize),
-}
-impl Grid {
-    pub fn new(x: usize, y: usize) -> Self {
-        let max = x + y * x;
-        let mut list = Vec::with_capacity(max);
-
-        for _ in 0..max {
-            list.push(None);
-        }
-        
-        Grid {
-            list: list,
-            len: (x, y),
-        }
-    }
-
-    fn bounds(&self, x: usize, y: usize) -> bool {
-        x < self.len.0 && y < self.len.1
-    }
-
-    fn index(&self, x: usize, y: usize) -> usize {
-        x + (y * self.len.0)
-    }
-
-    pub fn get(&self, x: usize, y: usize) -> Option<Entity> {
-        if self.bounds(x, y) {
-            self.list[self.index(x, y)]
-        }
-        else {
-            None
-        }
-    }
 
-    pub fn set(&mut self, x: usize, y: usize, element: Option<Entity>) {
-        if self.bounds(x, y) {
-            let index = self.index(x, y);
-            self.list[index] = element;
-        }
-    }
-
-    pub fn print(&self) {
-        for x in 0..self.len.0 {
-            for y in 0..self.len.1 {
-                match self.get(x, y) {
-                    Some(_) => print!("1 "),
-                    None => print!("0 "),
-                }
-            }
-            println!();
-        }
-    }
+    // Whether a snake has slowed time down, making ticks come less often.
+    pub slowed: bool,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Direction {
     pub direction: (i8, i8),
     pub previous: Option<(i8, i8)>,
@@ -97,33 +45,128 @@ impl Component for Controls {
     type Storage = DenseVecStorage<Self>;
 }
 
+// The part of a snake a segment is drawn as.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Piece {
+    Head,
+    Body,
+    Corner,
+    Tail,
+}
+
+// How a snake segment is drawn, the piece and how far it is turned (in radians).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sprite {
+    pub piece: Piece,
+    pub angle: f32,
+}
+impl Component for Sprite {
+    type Storage = DenseVecStorage<Self>;
+}
+
+// Which mesh and texture an entity's renderable was made from, so it can be replaced when that
+// changes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Appearance {
+    pub mesh: &'static str,
+    pub colour: String,
+}
+impl Component for Appearance {
+    type Storage = DenseVecStorage<Self>;
+}
+
+// A speck flying out of an effect, moving in tiles per second. The colour is the part of the theme
+// it is drawn with.
+pub struct Particle {
+    pub position: (f32, f32),
+    pub velocity: (f32, f32),
+    pub life: f32,
+    pub lifetime: f32,
+    pub colour: &'static str,
+}
+impl Component for Particle {
+    type Storage = DenseVecStorage<Self>;
+}
+
+// Flashes a snake segment on and off for the seconds remaining.
+pub struct Flash {
+    pub remaining: f32,
+}
+impl Component for Flash {
+    type Storage = DenseVecStorage<Self>;
+}
+
+// The backdrop drawn behind the board, which shows where the letterboxing starts.
+#[derive(Default)]
+pub struct Board;
+impl Component for Board {
+    type Storage = NullStorage<Self>;
+}
+
+// A line between the rows or columns of the board, numbered from the top left.
+pub struct GridLine {
+    pub vertical: bool,
+    pub index: usize,
+}
+impl Component for GridLine {
+    type Storage = DenseVecStorage<Self>;
+}
+
 pub struct Snake {
     // Which direction the snake will go next tick.
     pub queued_direction: (i8, i8),
 
     // Length of the snake.
-    pub length: u16,
+    pub length: u32,
+
+    // How much food the snake has eaten.
+    pub score: u32,
 
     // End of the snake (end of tail).
     pub end: Option<Entity>,
 }
+impl Snake {
+    // Queue a direction for the next tick, unless it would turn the snake back onto itself.
+    pub fn steer(&mut self, current: &Direction, wanted: (i8, i8)) -> bool {
+        let reverse = (-current.direction.0, -current.direction.1);
+        if wanted == (0, 0) || wanted == reverse {
+            return false;
+        }
+
+        self.queued_direction = wanted;
+        true
+    }
+}
 impl Component for Snake {
     type Storage = DenseVecStorage<Self>;
 }
 
+// Which player a snake (and every segment of it) belongs to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Player(pub usize);
+impl Component for Player {
+    type Storage = DenseVecStorage<Self>;
+}
+
 // Flags that the snake should be extended
 pub struct Extension(pub u16);
 impl Component for Extension {
     type Storage = DenseVecStorage<Self>;
 }
 
+// Flags that the snake should lose segments off the end of its tail.
+pub struct Shrink(pub u16);
+impl Component for Shrink {
+    type Storage = DenseVecStorage<Self>;
+}
+
 // Which kind of entity the tail inherits from.
 pub struct Parent(pub Entity);
 impl Component for Parent {
     type Storage = DenseVecStorage<Self>;
 }
 
-#[derive(Clone, Default)]
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct Tile {
     pub x: i32,
     pub y: i32,
@@ -132,14 +175,175 @@ impl Component for Tile {
     type Storage = DenseVecStorage<Self>;
 }
 
-#[derive(Debug)]
+// The tile an entity was on when the last tick started, for sliding it across from there.
+pub struct Previous(pub Tile);
+impl Component for Previous {
+    type Storage = DenseVecStorage<Self>;
+}
+
+// Food that disappears once it has been on the board for a while, with the ticks it has left.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Expiry {
+    pub remaining: u32,
+}
+impl Component for Expiry {
+    type Storage = DenseVecStorage<Self>;
+}
+
+// Food that shrinks the snake that eats it instead of growing it.
+#[derive(Default)]
+pub struct Poison;
+impl Component for Poison {
+    type Storage = NullStorage<Self>;
+}
+
+// Food that moves around the board by itself.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Live {
+    // Wanders around at random.
+    Wander,
+
+    // Runs away from snakes that get close, and wanders otherwise.
+    Flee,
+}
+impl Component for Live {
+    type Storage = DenseVecStorage<Self>;
+}
+
+// Power-ups a snake can pick up, each lasting a number of ticks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Power {
+    // Pass through its own body.
+    Ghost,
+
+    // Ticks come half as often for everyone.
+    SlowTime,
+
+    // Pulls nearby food towards the head.
+    Magnet,
+
+    // Survives running into one thing that would kill it.
+    Shield,
+
+    // Swaps the head and the tail when picked up, lasting only long enough to be noticed.
+    Reverse,
+}
+impl Power {
+    // How many ticks the power lasts for.
+    pub fn duration(&self) -> u32 {
+        match *self {
+            Power::Ghost => 40,
+            Power::SlowTime => 40,
+            Power::Magnet => 60,
+            Power::Shield => 150,
+            Power::Reverse => 8,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Power::Ghost => "ghost",
+            Power::SlowTime => "slow time",
+            Power::Magnet => "magnet",
+            Power::Shield => "shield",
+            Power::Reverse => "reverse",
+        }
+    }
+}
+
+// Power-ups a snake has, with how many ticks each has left. Only the head has this.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Powers(pub Vec<(Power, u32)>);
+impl Powers {
+    pub fn has(&self, power: Power) -> bool {
+        self.0.iter().any(|&(active, _)| active == power)
+    }
+
+    // Start a power, or start it over if the snake already has it.
+    pub fn grant(&mut self, power: Power) {
+        self.remove(power);
+        self.0.push((power, power.duration()));
+        self.0.sort();
+    }
+
+    pub fn remove(&mut self, power: Power) {
+        self.0.retain(|&(active, _)| active != power);
+    }
+
+    // Count down a tick, returning the powers that ran out.
+    pub fn tick(&mut self) -> Vec<Power> {
+        for &mut (_, ref mut remaining) in &mut self.0 {
+            *remaining = remaining.saturating_sub(1);
+        }
+        let expired = self.0.iter().filter(|&&(_, remaining)| remaining == 0).map(|&(power, _)| power).collect();
+        self.0.retain(|&(_, remaining)| remaining > 0);
+        expired
+    }
+}
+impl Component for Powers {
+    type Storage = DenseVecStorage<Self>;
+}
+
+// What kind of thing an entity is when it sits on the `Grid`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Type {
-    Kill,
-    Eat
+    // Snake segments, running into one kills the snake.
+    Snake,
+
+    // Walls and obstacles, also kills the snake.
+    Wall,
+
+    // Eating it extends the snake.
+    Food,
+
+    // Picking it up gives the snake a power.
+    PowerUp(Power),
+
+    // A snake's head entering it comes out of the other end of the pair, at this position.
+    Portal {
+        x: usize,
+        y: usize,
+    },
+
+    // A one-way gate, heads can only go into it heading this way and run into it otherwise.
+    Gate((i8, i8)),
+
+    // Heads on ice slide straight on, turning once they're off it.
+    Ice,
+
+    // A conveyor carries heads going across it one tile this way.
+    Conveyor((i8, i8)),
+}
+impl Type {
+    // Which layer of the grid this kind of entity lives on.
+    pub fn layer(&self) -> Layer {
+        match *self {
+            Type::Snake => Layer::Actor,
+            Type::Wall | Type::Portal { .. } | Type::Gate(_) | Type::Ice | Type::Conveyor(_) => Layer::Terrain,
+            Type::Food | Type::PowerUp(_) => Layer::Item,
+        }
+    }
+
+    // Whether running into this kills the snake.
+    pub fn kills(&self) -> bool {
+        match *self {
+            Type::Snake | Type::Wall => true,
+            Type::Food | Type::PowerUp(_) | Type::Portal { .. } | Type::Gate(_) | Type::Ice | Type::Conveyor(_) => false,
+        }
+    }
+
+    // Whether a head going into this heading a way kills the snake, which for gates depends on the
+    // way it's heading.
+    pub fn blocks(&self, heading: (i8, i8)) -> bool {
+        match *self {
+            Type::Gate(way) => heading != way,
+            _ => self.kills(),
+        }
+    }
 }
 impl Default for Type {
     fn default() -> Self {
-        Type::Kill
+        Type::Wall
     }
 }
 impl Component for Type {
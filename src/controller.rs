@@ -1,133 +1,264 @@
 
 
-use amethyst::ecs::resources::InputHandler;
-use specs::{Entities, Fetch, FetchMut, Join, System, ReadStorage, WriteStorage};
+use std::collections::{HashMap, HashSet};
 
-use components::{Controls, Direction, Extension, Grid, Parent, Tile, Type, Snake, Tick};
+use specs::{Entities, Entity, Fetch, FetchMut, Join, System, ReadStorage, WriteStorage};
 
+use components::{Direction, Parent, Player, Power, Powers, Previous, Tile, Type, Snake, Tick};
+use event::{Cause, Events, GameEvent};
+use grid::{Grid, Layer, Occupant};
+
+// Moves the snakes, publishing what they run into for the other rule systems to deal with.
 #[derive(Default)]
 pub struct ControllerSystem;
 impl<'a> System<'a> for ControllerSystem {
     type SystemData = (
         // Resources
         Entities<'a>,
-        Fetch<'a, InputHandler>,
-        FetchMut<'a, Tick>,
+        Fetch<'a, Tick>,
         FetchMut<'a, Grid>,
+        FetchMut<'a, Events>,
 
         // Components
         WriteStorage<'a, Tile>,
-        WriteStorage<'a, Snake>,
         WriteStorage<'a, Direction>,
-        WriteStorage<'a, Extension>,
-        ReadStorage<'a, Type>,
+        WriteStorage<'a, Previous>,
+        ReadStorage<'a, Snake>,
         ReadStorage<'a, Parent>,
-        ReadStorage<'a, Controls>,
+        ReadStorage<'a, Player>,
+        ReadStorage<'a, Powers>,
     );
     fn run(&mut self, data: Self::SystemData) {
         // Destructure the `Self::SystemData` into something more usable.
         let (
             entities, 
-            input, 
-            mut tick, 
+            tick, 
             mut grid, 
+            mut events,
             mut tiles, 
-            mut snakes, 
             mut directions, 
-            mut extensions,
-            types, 
-            parents, 
-            controls,
+            mut previous,
+            snakes,
+            parents,
+            players,
+            powers,
         ) = data;
 
-        // Iterate over snakes.
-        for (direction, snake, _, controls) in (&mut directions, &mut snakes, &mut tiles, &controls).join() {
-            // Figure out a valid direction for the snake.
-            match (
-                input.button_down(controls.left),
-                input.button_down(controls.right),
-                input.button_down(controls.up),
-                input.button_down(controls.down),
-            ) {
-                // Queue the direction for the next tick
-                (true, _, _, _) if direction.direction.0 != 1 => snake.queued_direction = (-1, 0),
-                (_, true, _, _) if direction.direction.0 != -1 => snake.queued_direction = (1, 0),
-                (_, _, true, _) if direction.direction.1 != 1 => snake.queued_direction = (0, -1),
-                (_, _, _, true) if direction.direction.1 != -1 => snake.queued_direction = (0, 1),
-                _ => { },
+        if tick.ticked {
+            // Remember where everything was and which way it was heading before this tick, so
+            // followers trail their parent's last move regardless of the order they are joined in.
+            let headings = (&*entities, &directions).join()
+                .map(|(entity, direction)| (entity, direction.direction))
+                .collect::<HashMap<Entity, (i8, i8)>>();
+            let before = (&*entities, &tiles).join()
+                .map(|(entity, tile)| (entity, tile.clone()))
+                .collect::<HashMap<Entity, Tile>>();
+            for (&entity, tile) in &before {
+                previous.insert(entity, Previous(tile.clone()));
             }
 
-            if tick.ticked {
-                // Update direction to the new queued direction.
+            // Update snake directions to the queued direction and tail directions to their parent's.
+            for (entity, direction) in (&*entities, &mut directions).join() {
+                let next = match snakes.get(entity) {
+                    // Heads on ice keep going the way they were, the turn waits until they're off it.
+                    Some(_) if before.get(&entity).and_then(|tile| terrain(&grid, tile)) == Some(Type::Ice) => {
+                        direction.direction
+                    },
+                    Some(snake) => snake.queued_direction,
+                    None => match parents.get(entity).and_then(|parent| headings.get(&parent.0)) {
+                        Some(heading) => *heading,
+                        None => continue,
+                    },
+                };
+
                 direction.previous = Some(direction.direction);
-                direction.direction = snake.queued_direction;
+                direction.direction = next;
             }
-        }
 
-        if tick.ticked {
-            // Update follower (tail) directions.
-            for (entity, parent, _) in (&*entities, &parents, &directions.check()).join() {
-                let parent_direction = match directions.get(parent.0) {
-                    Some(direction) => Some(direction.clone()),
-                    None => None,
+            // Move the heads first so they collide with the board as it was before this tick, with
+            // every head and tail still where it was. A head going into the tile the end of a tail
+            // is about to leave runs into it, its own tail included. Nothing is put on the grid
+            // until every head knows where it's going, so which head is joined first doesn't decide
+            // anything.
+            let mut moves = Vec::new();
+            for (entity, direction, tile, _) in (&*entities, &directions, &mut tiles, &snakes).join() {
+                let from = tile.clone();
+                tile.x += direction.direction.0 as i32;
+                tile.y += direction.direction.1 as i32;
+
+                // Conveyors carry heads going across them out to one side. Heads going along them
+                // pass straight over, rather than being carried on ahead or back into themselves.
+                if let Some(Type::Conveyor(way)) = terrain(&grid, tile) {
+                    let (heading_x, heading_y) = direction.direction;
+                    if way.0 * heading_x + way.1 * heading_y == 0 {
+                        tile.x += way.0 as i32;
+                        tile.y += way.1 as i32;
+                    }
+                }
+
+                // Heads going into a portal come straight out of the other end, still heading the
+                // same way.
+                if let Some(Type::Portal { x, y }) = terrain(&grid, tile) {
+                    let entrance = tile.clone();
+                    tile.x = x as i32;
+                    tile.y = y as i32;
+                    events.publish(GameEvent::Teleported { snake: entity, from: entrance, to: tile.clone() });
+                }
+
+                let (x, y) = (tile.x as usize, tile.y as usize);
+                events.publish(GameEvent::SnakeMoved { snake: entity, from: from.clone(), to: tile.clone() });
+
+                // End game if snake hits a wall.
+                if !grid.bounds(x, y) {
+                    moves.push((entity, from, tile.clone(), Outcome::Dies(Cause::Edge)));
+                    continue;
+                }
+
+                let has = |power| powers.get(entity).map(|powers| powers.has(power)).unwrap_or(false);
+                let outcome = match grid.occupant_kind(x, y) {
+                    // Ghosts pass through their own body.
+                    Some(Type::Snake) if has(Power::Ghost) && owned(&grid, &players, entity, x, y) => {
+                        Outcome::Overlaps { shield: false }
+                    },
+                    // A shield takes the hit instead, letting the snake through this once.
+                    Some(kind) if kind.blocks(direction.direction) && has(Power::Shield) => {
+                        Outcome::Overlaps { shield: true }
+                    },
+                    // Dead heads stay off the grid so whatever they ran into keeps its tile.
+                    Some(kind) if kind.blocks(direction.direction) => Outcome::Dies(Cause::Hit(kind)),
+                    Some(Type::Food) | Some(Type::PowerUp(_)) => match grid.get(Layer::Item, x, y) {
+                        Some(item) => Outcome::Reaches(item),
+                        None => Outcome::Moves,
+                    },
+                    _ => Outcome::Moves,
                 };
+                moves.push((entity, from, tile.clone(), outcome));
+            }
+
+            // Heads meeting in the same tile all run into each other, unless a shield takes the hit.
+            let mut meeting = HashMap::new();
+            for &(_, _, ref to, ref outcome) in &moves {
+                if outcome.survives() {
+                    *meeting.entry((to.x, to.y)).or_insert(0) += 1;
+                }
+            }
+            for &mut (entity, _, ref to, ref mut outcome) in &mut moves {
+                if meeting.get(&(to.x, to.y)).cloned().unwrap_or(0) < 2 {
+                    continue;
+                }
 
-                // Get the directino of the parent.
-                if let Some(parent_direction) = parent_direction {
-                    let mut direction = directions.get_mut(entity).unwrap();
-                    direction.previous = Some(direction.direction);
+                let shielded = powers.get(entity).map(|powers| powers.has(Power::Shield)).unwrap_or(false);
+                *outcome = match *outcome {
+                    Outcome::Overlaps { shield: true } => Outcome::Dies(Cause::Hit(Type::Snake)),
+                    _ if shielded => Outcome::Overlaps { shield: true },
+                    _ => Outcome::Dies(Cause::Hit(Type::Snake)),
+                };
+            }
 
-                    // Use the parent's previous direction so it follows the path the parent took.
-                    if let Some(previous) = parent_direction.previous {
-                        direction.direction = previous;
-                    }
+            for &(entity, _, ref to, ref outcome) in &moves {
+                match *outcome {
+                    Outcome::Dies(cause) => {
+                        events.publish(GameEvent::SnakeDied { who: entity, cause: cause, tile: to.clone() });
+                    },
+                    Outcome::Overlaps { shield: true } => {
+                        events.publish(GameEvent::ShieldBroken { snake: entity, tile: to.clone() });
+                    },
+                    Outcome::Reaches(item) => match item.kind {
+                        Type::PowerUp(power) => events.publish(GameEvent::PowerUpCollected {
+                            by: entity,
+                            item: item.entity,
+                            power: power,
+                            tile: to.clone(),
+                        }),
+                        kind => events.publish(GameEvent::FoodEaten {
+                            by: entity,
+                            food: item.entity,
+                            kind: kind,
+                            tile: to.clone(),
+                        }),
+                    },
+                    _ => { },
                 }
             }
 
-            // Update tiles to correct positions.
-            for (entity, direction, tile) in (&*entities, &directions, &mut tiles).join() {
-                // Only remove the entity from the previous portion of the grid if it actually this entity
-                // in that piece of the grid.
-                if let Some(other) = grid.get(tile.x as usize, tile.y as usize) {
-                    if other == entity {
-                        grid.set(tile.x as usize, tile.y as usize, None);
-                    }
+            // Heads with something following them are left where they were on the grid until their
+            // neck moves in behind them, the rest leave their tile now. Heads that died stay off the
+            // grid.
+            let followed = parents.join().map(|parent| parent.0).collect::<HashSet<Entity>>();
+            for &(entity, ref from, _, _) in &moves {
+                if !followed.contains(&entity) {
+                    grid.remove(from.x as usize, from.y as usize, entity);
+                }
+            }
+            for &(entity, _, ref to, ref outcome) in &moves {
+                if outcome.survives() {
+                    grid.insert(to.x as usize, to.y as usize, entity, Type::Snake);
                 }
+            }
 
-                tile.x += direction.direction.0 as i32;
-                tile.y += direction.direction.1 as i32;
+            // Then move the tails along into the space the heads left behind. Each segment takes
+            // the tile its parent was on, which isn't always next to it when the parent went
+            // through a portal or over a conveyor.
+            for (entity, tile, parent) in (&*entities, &mut tiles, &parents).join() {
+                let next = match before.get(&parent.0) {
+                    Some(next) => next.clone(),
+                    None => continue,
+                };
 
-                // Only if the current tile is a snake.
-                if snakes.get(entity).is_some() {
-                    if let Some(other) = grid.get(tile.x as usize, tile.y as usize) {
-                        // Check if the entity kills or is eatable.
-                        if let Some(t) = types.get(other) {
-                            match *t {
-                                // TODO: Stop the game.
-                                Type::Kill => {
-                                    tick.game_over = true;
-                                    println!("Game over for {:?} killed by {:?}", entity, other);
-                                },
-
-                                // TODO: Implement extending the snake and score.
-                                Type::Eat => {
-                                    entities.delete(other);
-                                    extensions.insert(entity, Extension(5));
-                                    println!("Eat {:?}", other);
-                                },
-                            }
-                        }
-                    }
+                grid.remove(tile.x as usize, tile.y as usize, entity);
+                *tile = next;
+                grid.insert(tile.x as usize, tile.y as usize, entity, Type::Snake);
+            }
 
-                    // End game if snake hits a wall.
-                    if tile.x < 0 || tile.x >= grid.len.0 as i32 || tile.y < 0 || tile.y >= grid.len.1 as i32 {
-                        tick.game_over = true;
-                    }
+            // Heads sharing a tile with something they were allowed through are put back on top once
+            // the tails have moved.
+            for &(entity, _, ref to, ref outcome) in &moves {
+                if let Outcome::Overlaps { .. } = *outcome {
+                    grid.insert(to.x as usize, to.y as usize, entity, Type::Snake);
                 }
-                
-                // Add entity to its new tile in the grid.
-                grid.set(tile.x as usize, tile.y as usize, Some(entity));
             }
         }
     }
 }
+
+// What happens to a head moving into the tile it's heading for.
+#[derive(Clone, Copy, Debug)]
+enum Outcome {
+    // Into an empty tile, or over terrain that lets it through.
+    Moves,
+
+    // Onto food or a power-up.
+    Reaches(Occupant),
+
+    // Through something it was allowed through, sharing the tile with it.
+    Overlaps {
+        shield: bool,
+    },
+
+    Dies(Cause),
+}
+impl Outcome {
+    fn survives(&self) -> bool {
+        match *self {
+            Outcome::Dies(_) => false,
+            _ => true,
+        }
+    }
+}
+
+// Whether the snake segment at a position belongs to the same player as a snake.
+fn owned(grid: &Grid, players: &ReadStorage<Player>, snake: Entity, x: usize, y: usize) -> bool {
+    let owner = players.get(snake);
+    grid.get(Layer::Actor, x, y)
+        .map(|occupant| owner.is_some() && players.get(occupant.entity) == owner)
+        .unwrap_or(false)
+}
+
+// What the board is made of under a position, if it's anything but open floor.
+fn terrain(grid: &Grid, tile: &Tile) -> Option<Type> {
+    if tile.x < 0 || tile.y < 0 {
+        return None;
+    }
+
+    grid.get(Layer::Terrain, tile.x as usize, tile.y as usize).map(|occupant| occupant.kind)
+}
@@ -0,0 +1,294 @@
+
+// Effects that play in response to game events: particle bursts, screen shake, flashing the snake
+// that died and sound cues. None of them touch the game rules, so they stay out of the simulation
+// and each one can be switched off on its own.
+//
+// Sound needs the `sound` feature, without it the cues are silent.
+
+use std::time::Duration;
+
+use amethyst::ecs::components::LocalTransform;
+use amethyst::ecs::resources::{Camera, Time};
+use rand::{self, Rng};
+use specs::{Entities, Fetch, FetchMut, Join, ReadStorage, System, WriteStorage};
+
+use body::Followers;
+use components::{Flash, Parent, Particle, Power, Snake};
+use event::{Events, GameEvent, Reader};
+use power;
+use tile::Layout;
+
+// How long the screen shakes for and how far it moves at first, as a fraction of a tile.
+pub const SHAKE_TIME: f32 = 0.4;
+pub const SHAKE_STRENGTH: f32 = 0.3;
+
+// How long a snake flashes for after dying and how many times a second.
+pub const FLASH_TIME: f32 = 1.5;
+pub const FLASH_RATE: f32 = 8.0;
+
+// Which effects are switched on.
+#[derive(Clone, Debug)]
+pub struct Effects {
+    pub particles: bool,
+    pub shake: bool,
+    pub flash: bool,
+    pub sound: bool,
+}
+impl Default for Effects {
+    fn default() -> Self {
+        Effects {
+            particles: true,
+            shake: true,
+            flash: true,
+            sound: true,
+        }
+    }
+}
+
+// How much longer the screen shakes for.
+#[derive(Clone, Debug, Default)]
+pub struct Shake {
+    pub remaining: f32,
+}
+
+fn seconds(duration: Duration) -> f32 {
+    duration.as_secs() as f32 + duration.subsec_nanos() as f32 / 1_000_000_000.0
+}
+
+// Bursts of particles flying out of where things happen.
+#[derive(Default)]
+pub struct ParticleSystem {
+    reader: Reader,
+}
+impl<'a> System<'a> for ParticleSystem {
+    type SystemData = (
+        // Resources
+        Entities<'a>,
+        Fetch<'a, Events>,
+        Fetch<'a, Effects>,
+        Fetch<'a, Time>,
+        Fetch<'a, Layout>,
+
+        // Components
+        WriteStorage<'a, Particle>,
+        WriteStorage<'a, LocalTransform>,
+    );
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, events, effects, time, layout, mut particles, mut locals) = data;
+
+        // Effects don't use the game's random numbers, so they can't change how a game plays out.
+        let mut random = rand::thread_rng();
+        for event in events.read(&mut self.reader) {
+            let (tile, count, speed, colour) = match event {
+                GameEvent::FoodEaten { ref tile, .. } => (tile.clone(), 12, 3.0, "food"),
+                GameEvent::FoodSpawned { ref tile, .. } => (tile.clone(), 6, 1.5, "food"),
+                GameEvent::FoodExpired { ref tile, .. } => (tile.clone(), 6, 1.0, "food"),
+                GameEvent::SnakeDied { ref tile, .. } => (tile.clone(), 24, 4.0, "walls"),
+                GameEvent::PowerUpCollected { ref tile, power, .. } => (tile.clone(), 12, 3.0, power::colour(power)),
+                GameEvent::ShieldBroken { ref tile, .. } => (tile.clone(), 16, 3.0, power::colour(Power::Shield)),
+                GameEvent::Teleported { ref to, .. } => (to.clone(), 8, 2.0, "portal"),
+                _ => continue,
+            };
+
+            if !effects.particles {
+                continue;
+            }
+
+            for _ in 0..count {
+                let angle = random.gen_range(0.0, 2.0 * ::std::f32::consts::PI);
+                let speed = random.gen_range(0.5, 1.0) * speed;
+                let lifetime = random.gen_range(0.3, 0.6);
+                let particle = entities.create();
+                particles.insert(particle, Particle {
+                    position: (tile.x as f32, tile.y as f32),
+                    velocity: (angle.cos() * speed, angle.sin() * speed),
+                    life: lifetime,
+                    lifetime: lifetime,
+                    colour: colour,
+                });
+            }
+        }
+
+        let delta = seconds(time.delta_time);
+        for (entity, particle) in (&*entities, &mut particles).join() {
+            particle.life -= delta;
+            if particle.life <= 0.0 || !effects.particles {
+                entities.delete(entity);
+                continue;
+            }
+
+            particle.position.0 += particle.velocity.0 * delta;
+            particle.position.1 += particle.velocity.1 * delta;
+
+            // Particles only get a transform once they've been given something to be drawn with.
+            if let Some(local) = locals.get_mut(entity) {
+                let (x, y) = layout.position(particle.position.0, particle.position.1);
+                let size = layout.tile * 0.25 * particle.life / particle.lifetime;
+                local.translation = [x, y, 0.2];
+                local.scale = [size, size, 1.0];
+            }
+        }
+    }
+}
+
+// Shakes the camera when a snake dies, and a little when a shield breaks or the arena closes in.
+#[derive(Default)]
+pub struct ShakeSystem {
+    reader: Reader,
+}
+impl<'a> System<'a> for ShakeSystem {
+    type SystemData = (
+        // Resources
+        Fetch<'a, Events>,
+        Fetch<'a, Effects>,
+        Fetch<'a, Time>,
+        Fetch<'a, Layout>,
+        FetchMut<'a, Shake>,
+        FetchMut<'a, Camera>,
+    );
+    fn run(&mut self, (events, effects, time, layout, mut shake, mut camera): Self::SystemData) {
+        for event in events.read(&mut self.reader) {
+            match event {
+                GameEvent::SnakeDied { .. } => shake.remaining = SHAKE_TIME,
+                GameEvent::ShieldBroken { .. } | GameEvent::ArenaClosed { .. } => shake.remaining = SHAKE_TIME / 2.0,
+                _ => { },
+            }
+        }
+
+        if !effects.shake {
+            shake.remaining = 0.0;
+        }
+
+        let (mut x, mut y) = (0.0, 0.0);
+        if shake.remaining > 0.0 {
+            shake.remaining -= seconds(time.delta_time);
+
+            // Settle down as the shake runs out.
+            let strength = layout.tile * SHAKE_STRENGTH * shake.remaining.max(0.0) / SHAKE_TIME;
+            let mut random = rand::thread_rng();
+            x = random.gen_range(-1.0, 1.0) * strength;
+            y = random.gen_range(-1.0, 1.0) * strength;
+        }
+
+        camera.eye = [x, y, 1.0];
+        camera.target = [x, y, 0.0];
+    }
+}
+
+// Flashes every segment of a snake that died.
+#[derive(Default)]
+pub struct FlashSystem {
+    reader: Reader,
+}
+impl<'a> System<'a> for FlashSystem {
+    type SystemData = (
+        // Resources
+        Entities<'a>,
+        Fetch<'a, Events>,
+        Fetch<'a, Effects>,
+        Fetch<'a, Time>,
+
+        // Components
+        ReadStorage<'a, Parent>,
+        ReadStorage<'a, Snake>,
+        WriteStorage<'a, Flash>,
+    );
+    fn run(&mut self, (entities, events, effects, time, parents, snakes, mut flashes): Self::SystemData) {
+        for event in events.read(&mut self.reader) {
+            let snake = match event {
+                GameEvent::SnakeDied { who, .. } => who,
+                _ => continue,
+            };
+
+            if !effects.flash {
+                continue;
+            }
+
+            let end = snakes.get(snake).and_then(|snake| snake.end);
+            for segment in Followers::new(&entities, &parents).body(snake, end) {
+                flashes.insert(segment, Flash { remaining: FLASH_TIME });
+            }
+        }
+
+        let delta = seconds(time.delta_time);
+        let mut done = Vec::new();
+        for (entity, flash) in (&*entities, &mut flashes).join() {
+            flash.remaining -= delta;
+            if flash.remaining <= 0.0 || !effects.flash {
+                done.push(entity);
+            }
+        }
+        for entity in done {
+            flashes.remove(entity);
+        }
+    }
+}
+
+// The tone played for an event, its pitch in hertz and how long it lasts.
+pub fn cue(event: &GameEvent) -> Option<(u32, Duration)> {
+    match *event {
+        GameEvent::FoodEaten { .. } => Some((880, Duration::from_millis(80))),
+        GameEvent::SnakeDied { .. } => Some((220, Duration::from_millis(400))),
+        GameEvent::GameOver => Some((110, Duration::from_millis(600))),
+        GameEvent::FoodSpawned { .. } => Some((660, Duration::from_millis(40))),
+        GameEvent::FoodExpired { .. } => Some((330, Duration::from_millis(60))),
+        GameEvent::LevelUp { .. } => Some((1320, Duration::from_millis(150))),
+        GameEvent::PowerUpCollected { .. } => Some((990, Duration::from_millis(120))),
+        GameEvent::PowerExpired { .. } => Some((440, Duration::from_millis(80))),
+        GameEvent::ShieldBroken { .. } => Some((330, Duration::from_millis(200))),
+        GameEvent::Teleported { .. } => Some((770, Duration::from_millis(60))),
+        GameEvent::ArenaWarning { .. } => Some((550, Duration::from_millis(100))),
+        GameEvent::ArenaClosed { .. } => Some((165, Duration::from_millis(300))),
+        GameEvent::SnakeSteered { .. } |
+        GameEvent::SnakeMoved { .. } |
+        GameEvent::FoodSwallowed { .. } |
+        GameEvent::FoodMoved { .. } |
+        GameEvent::PowerUpSpawned { .. } => None,
+    }
+}
+
+// Plays a short tone for each event.
+pub struct SoundSystem {
+    reader: Reader,
+
+    #[cfg(feature = "sound")]
+    device: Option<::rodio::Device>,
+}
+impl Default for SoundSystem {
+    fn default() -> Self {
+        SoundSystem {
+            reader: Reader::default(),
+
+            #[cfg(feature = "sound")]
+            device: ::rodio::default_output_device(),
+        }
+    }
+}
+impl SoundSystem {
+    #[cfg(feature = "sound")]
+    fn play(&self, (pitch, length): (u32, Duration)) {
+        use rodio::Source;
+        use rodio::source::SineWave;
+
+        if let Some(ref device) = self.device {
+            ::rodio::play_raw(device, SineWave::new(pitch).take_duration(length).amplify(0.2));
+        }
+    }
+
+    #[cfg(not(feature = "sound"))]
+    fn play(&self, _: (u32, Duration)) { }
+}
+impl<'a> System<'a> for SoundSystem {
+    type SystemData = (
+        // Resources
+        Fetch<'a, Events>,
+        Fetch<'a, Effects>,
+    );
+    fn run(&mut self, (events, effects): Self::SystemData) {
+        for event in events.read(&mut self.reader) {
+            if let (true, Some(cue)) = (effects.sound, cue(&event)) {
+                self.play(cue);
+            }
+        }
+    }
+}
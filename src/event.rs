@@ -0,0 +1,189 @@
+
+// Things that happen in the game, published by the rule systems for anything that wants to react
+// to them. The outcomes of a move are applied by the systems that subscribe to them, and effects,
+// sounds, stats and so on can be added the same way without touching the rules.
+//
+// Readers keep their own place in the queue so any number of them can see every event. Every event
+// from this tick and the last is kept however many there are, so readers that read once a tick never
+// miss one, and only the most recent of the older events are kept so a reader that stops reading
+// doesn't keep them alive.
+
+use std::collections::VecDeque;
+
+use specs::Entity;
+
+use components::{Power, Tile, Type};
+
+// How many events from before the last tick are kept around for readers that haven't caught up yet.
+pub const CAPACITY: usize = 1024;
+
+// Foods a snake eats for each level.
+pub const LEVEL_SIZE: u32 = 5;
+
+// Why a snake died.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Cause {
+    // Ran off the edge of the board.
+    Edge,
+
+    // Ran into something that kills.
+    Hit(Type),
+
+    // Shrank away to nothing behind its head.
+    Shrunk,
+
+    // Was caught with its head in a ring of the arena as it closed.
+    Crushed,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum GameEvent {
+    // A player queued a new direction for their snake.
+    SnakeSteered {
+        snake: Entity,
+        direction: (i8, i8),
+    },
+
+    // A snake's head moved from one tile to the next, its body follows along behind.
+    SnakeMoved {
+        snake: Entity,
+        from: Tile,
+        to: Tile,
+    },
+
+    // A snake's head went into a portal and came out of the other end.
+    Teleported {
+        snake: Entity,
+        from: Tile,
+        to: Tile,
+    },
+
+    // A snake's head reached food, which hasn't been taken off the board yet.
+    FoodEaten {
+        by: Entity,
+        food: Entity,
+        kind: Type,
+        tile: Tile,
+    },
+
+    // The first snake to reach a food took it off the board, growing from it or shrinking if it was
+    // poison.
+    FoodSwallowed {
+        by: Entity,
+        food: Entity,
+        poison: bool,
+    },
+
+    // A snake died with its head on a tile.
+    SnakeDied {
+        who: Entity,
+        cause: Cause,
+        tile: Tile,
+    },
+
+    // The first death ended the game.
+    GameOver,
+
+    FoodSpawned {
+        food: Entity,
+        tile: Tile,
+    },
+
+    // Live food moved to the next tile.
+    FoodMoved {
+        food: Entity,
+        from: Tile,
+        to: Tile,
+    },
+
+    // Food was left too long and disappeared.
+    FoodExpired {
+        food: Entity,
+        tile: Tile,
+    },
+
+    // A snake has eaten enough to reach the next level.
+    LevelUp {
+        snake: Entity,
+        level: u32,
+    },
+
+    PowerUpSpawned {
+        item: Entity,
+        power: Power,
+        tile: Tile,
+    },
+
+    // A snake's head reached a power-up, which hasn't been taken off the board yet.
+    PowerUpCollected {
+        by: Entity,
+        item: Entity,
+        power: Power,
+        tile: Tile,
+    },
+
+    // A snake's power ran out.
+    PowerExpired {
+        snake: Entity,
+        power: Power,
+    },
+
+    // A snake's shield saved it from running into something.
+    ShieldBroken {
+        snake: Entity,
+        tile: Tile,
+    },
+
+    // A ring of the arena is about to close, in this many ticks.
+    ArenaWarning {
+        ring: usize,
+        ticks: u64,
+    },
+
+    // A ring of the arena closed and is all walls now.
+    ArenaClosed {
+        ring: usize,
+    },
+}
+
+// Where a reader is up to in the event queue.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Reader(usize);
+
+#[derive(Debug, Default)]
+pub struct Events {
+    // How many events were dropped off the front of the queue.
+    dropped: usize,
+    events: VecDeque<GameEvent>,
+
+    // Where the last tick's events and this tick's start, counting dropped events.
+    last_tick: usize,
+    this_tick: usize,
+}
+impl Events {
+    pub fn publish(&mut self, event: GameEvent) {
+        while self.events.len() >= CAPACITY && self.dropped < self.last_tick {
+            self.events.pop_front();
+            self.dropped += 1;
+        }
+        self.events.push_back(event);
+    }
+
+    // Start a new tick, letting go of events from before the last one once there are too many.
+    pub fn next_tick(&mut self) {
+        self.last_tick = self.this_tick;
+        self.this_tick = self.dropped + self.events.len();
+    }
+
+    // A reader that only sees events published from now on.
+    pub fn reader(&self) -> Reader {
+        Reader(self.dropped + self.events.len())
+    }
+
+    // Every event published since the reader last read, moving it up to date.
+    pub fn read(&self, reader: &mut Reader) -> Vec<GameEvent> {
+        let start = reader.0.max(self.dropped) - self.dropped;
+        reader.0 = self.dropped + self.events.len();
+        self.events.iter().skip(start).cloned().collect()
+    }
+}
@@ -1,5 +1,4 @@
 
-use amethyst::ecs::components::{LocalTransform, Renderable, Transform};
 use specs::{Entities, Fetch, Join, System, WriteStorage};
 
 use components::*;
@@ -15,12 +14,10 @@ impl<'a> System<'a> for ExtendSystem {
         // Components
         WriteStorage<'a, Type>,
         WriteStorage<'a, Extension>,
-        WriteStorage<'a, LocalTransform>,
-        WriteStorage<'a, Transform>,
-        WriteStorage<'a, Renderable>,
         WriteStorage<'a, Tile>,
         WriteStorage<'a, Direction>,
         WriteStorage<'a, Parent>,
+        WriteStorage<'a, Player>,
         WriteStorage<'a, Snake>,
     );
     fn run(&mut self, data: Self::SystemData) {
@@ -30,12 +27,10 @@ impl<'a> System<'a> for ExtendSystem {
             tick, 
             mut types,
             mut extensions, 
-            mut locals, 
-            mut globals, 
-            mut renderables, 
             mut tiles, 
             mut directions, 
             mut parents, 
+            mut players,
             mut snakes
         ) = data;
 
@@ -43,7 +38,7 @@ impl<'a> System<'a> for ExtendSystem {
             let mut remove = Vec::new();
             
             // Iterate over any entity that is a snake and needs to be extended.
-            for (entity, snake, extend, _, _, _) in (
+            for (entity, snake, extend, _, _) in (
                 &*entities, 
                 &mut snakes,
                 &mut extensions,
@@ -51,7 +46,6 @@ impl<'a> System<'a> for ExtendSystem {
                 // Only check that the entity has the component, but without borrowing the storage.
                 &tiles.check(), 
                 &directions.check(),
-                &renderables.check(),
             ).join() {
                 let current = match snake.end {
                     Some(end) => end,
@@ -61,24 +55,22 @@ impl<'a> System<'a> for ExtendSystem {
                 // Create a "tail" entity.
                 let extend_entity = entities.create();
                 let parent_tile = tiles.get(current).unwrap().clone();
-                let parent_direction = directions.get(current).unwrap().clone();
-                let parent_renderable = renderables.get(current).unwrap().clone();
+                let player = players.get(entity).cloned();
 
                 // Somewhat unfortunate that we have to borrow these storages mutably to insert.
                 // Eventually use a lazy insertion so the storages don't block other systems.
-                types.insert(extend_entity, Type::Kill);
-                locals.insert(extend_entity, LocalTransform::default());
-                globals.insert(extend_entity, Transform::default());
-                renderables.insert(extend_entity, parent_renderable);
-                tiles.insert(extend_entity, Tile {
-                    x: parent_tile.x - parent_direction.direction.0 as i32,
-                    y: parent_tile.y - parent_direction.direction.1 as i32,
-                });
+                types.insert(extend_entity, Type::Snake);
+                // Start under the end of the tail, it moves out onto the board once the end has
+                // moved on.
+                tiles.insert(extend_entity, parent_tile);
                 directions.insert(extend_entity, Direction {
                     direction: (0, 0),
                     previous: None,
                 });
                 parents.insert(extend_entity, Parent(current));
+                if let Some(player) = player {
+                    players.insert(extend_entity, player);
+                }
 
                 extend.0 -= 1;
                 if extend.0 == 0 {
@@ -86,6 +78,7 @@ impl<'a> System<'a> for ExtendSystem {
                 }
 
                 snake.end = Some(extend_entity);
+                snake.length += 1;
             }
 
             // Clean up the extension component when it hits 0.
@@ -0,0 +1,336 @@
+
+use std::collections::HashSet;
+
+use rand::Rng;
+use specs::{Entities, Fetch, FetchMut, Join, ReadStorage, System, WriteStorage};
+
+use components::{Direction, Expiry, Live, Poison, Snake, Tile, Tick, Type};
+use event::{Events, GameEvent, Reader};
+use grid::{Grid, Layer};
+use level::Level;
+use random::Random;
+
+// When new food is placed.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum When {
+    // Keep this many foods on the board at all times.
+    Keep(usize),
+
+    // Place a food whenever one is eaten, and one whenever there are none left so there is always
+    // something to eat.
+    OnEat,
+
+    // Place a food every so many ticks, until there are as many as the most allowed.
+    Interval {
+        ticks: u64,
+        max: usize,
+    },
+}
+
+// Where new food is placed, anywhere on the board if there's nowhere that fits.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Where {
+    Anywhere,
+
+    // At least this many tiles from every snake's head.
+    AwayFromHeads(i32),
+
+    // Within this many tiles of a snake's head.
+    NearHeads(i32),
+
+    // In one of the level's zones, picked by their weights.
+    Zones,
+}
+
+// How food is spawned, set by the game mode.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SpawnRules {
+    pub when: When,
+    pub place: Where,
+
+    // One in how many foods expire, are live or are poison, never if zero.
+    pub expiring: u32,
+    pub live: u32,
+    pub poison: u32,
+}
+impl Default for SpawnRules {
+    fn default() -> Self {
+        SpawnRules {
+            when: When::Interval { ticks: 50, max: 5 },
+            place: Where::Anywhere,
+            expiring: 3,
+            live: 4,
+            poison: 8,
+        }
+    }
+}
+
+// How many ticks expiring food lasts and how long it blinks for at the end.
+pub const EXPIRY: u32 = 80;
+pub const BLINK: u32 = 20;
+
+// How many ticks live food waits between moves so snakes can catch it.
+pub const LIVE_INTERVAL: u64 = 2;
+
+// How close a snake's head gets before fleeing food runs, and one in how many moves wandering food
+// turns.
+pub const FLEE_RANGE: i32 = 5;
+pub const WANDER_TURN: u32 = 4;
+
+// Whether expiring food is drawn this tick, it blinks on and off as it is about to disappear.
+pub fn visible(expiry: &Expiry) -> bool {
+    expiry.remaining > BLINK || expiry.remaining / 2 % 2 == 0
+}
+
+// Whether something happens that is one in `chance` likely.
+fn one_in(random: &mut Random, chance: u32) -> bool {
+    chance > 0 && random.gen_weighted_bool(chance)
+}
+
+// Picks a free tile for food following the spawn rules.
+fn place(place: &Where, grid: &Grid, level: &Level, heads: &[Tile], random: &mut Random) -> Option<(usize, usize)> {
+    let free = grid.free();
+    let distance = |x: usize, y: usize| heads.iter()
+        .map(|head| (head.x - x as i32).abs() + (head.y - y as i32).abs())
+        .min()
+        .unwrap_or(i32::max_value());
+
+    let fits = match *place {
+        Where::Anywhere => free.clone(),
+        Where::AwayFromHeads(range) => free.iter().cloned().filter(|&(x, y)| distance(x, y) >= range).collect(),
+        Where::NearHeads(range) => free.iter().cloned().filter(|&(x, y)| distance(x, y) <= range).collect(),
+        Where::Zones => {
+            let zones = level.zones.iter()
+                .map(|zone| (zone.weight, free.iter().cloned().filter(|&(x, y)| zone.contains(x, y)).collect::<Vec<_>>()))
+                .filter(|&(weight, ref tiles)| weight > 0 && !tiles.is_empty())
+                .collect::<Vec<_>>();
+
+            let total = zones.iter().map(|&(weight, _)| weight).sum::<u32>();
+            let mut pick = if total > 0 { random.gen_range(0, total) } else { 0 };
+            zones.into_iter()
+                .find(|&(weight, _)| if pick < weight { true } else { pick -= weight; false })
+                .map(|(_, tiles)| tiles)
+                .unwrap_or_default()
+        },
+    };
+
+    random.choose(if fits.is_empty() { &free } else { &fits }).cloned()
+}
+
+// Places food on empty parts of the grid, as often and wherever the spawn rules say.
+#[derive(Default)]
+pub struct FoodSpawner {
+    reader: Reader,
+}
+impl<'a> System<'a> for FoodSpawner {
+    type SystemData = (
+        // Resources
+        Entities<'a>,
+        Fetch<'a, Tick>,
+        Fetch<'a, SpawnRules>,
+        Fetch<'a, Level>,
+        FetchMut<'a, Grid>,
+        FetchMut<'a, Random>,
+        FetchMut<'a, Events>,
+
+        // Components
+        ReadStorage<'a, Snake>,
+        WriteStorage<'a, Tile>,
+        WriteStorage<'a, Type>,
+        WriteStorage<'a, Expiry>,
+        WriteStorage<'a, Live>,
+        WriteStorage<'a, Direction>,
+        WriteStorage<'a, Poison>,
+    );
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            tick,
+            rules,
+            level,
+            mut grid,
+            mut random,
+            mut events,
+            snakes,
+            mut tiles,
+            mut types,
+            mut expiries,
+            mut lives,
+            mut directions,
+            mut poisons,
+        ) = data;
+
+        // Every food eaten, once even if more than one snake reached it.
+        let eaten = events.read(&mut self.reader).into_iter()
+            .filter_map(|event| match event {
+                GameEvent::FoodEaten { food, .. } => Some(food),
+                _ => None,
+            })
+            .collect::<HashSet<_>>();
+
+        if !tick.ticked {
+            return;
+        }
+
+        let placed = (&*entities, &types, &tiles).join()
+            .filter(|&(entity, kind, tile)| *kind == Type::Food && grid.holds(Layer::Item, tile.x as usize, tile.y as usize, entity))
+            .count();
+
+        let wanted = match rules.when {
+            When::Keep(count) => count.saturating_sub(placed),
+            When::OnEat => if placed == 0 { eaten.len().max(1) } else { eaten.len() },
+            When::Interval { ticks, max } => if ticks > 0 && tick.ticks % ticks == 0 && placed < max { 1 } else { 0 },
+        };
+
+        let heads = (&snakes, &tiles).join().map(|(_, tile)| tile.clone()).collect::<Vec<_>>();
+        for _ in 0..wanted {
+            let (x, y) = match place(&rules.place, &grid, &level, &heads, &mut random) {
+                Some(tile) => tile,
+                None => break,
+            };
+
+            let food = entities.create();
+            tiles.insert(food, Tile { x: x as i32, y: y as i32 });
+            types.insert(food, Type::Food);
+            grid.insert(x, y, food, Type::Food);
+
+            if one_in(&mut random, rules.expiring) {
+                expiries.insert(food, Expiry { remaining: EXPIRY });
+            }
+            if one_in(&mut random, rules.live) {
+                lives.insert(food, if random.gen() { Live::Wander } else { Live::Flee });
+                directions.insert(food, Direction { direction: (0, 0), previous: None });
+            }
+            if one_in(&mut random, rules.poison) {
+                poisons.insert(food, Poison);
+            }
+
+            events.publish(GameEvent::FoodSpawned { food: food, tile: Tile { x: x as i32, y: y as i32 } });
+        }
+    }
+}
+
+// Takes food off the board once it has expired.
+#[derive(Default)]
+pub struct ExpirySystem;
+impl<'a> System<'a> for ExpirySystem {
+    type SystemData = (
+        // Resources
+        Entities<'a>,
+        Fetch<'a, Tick>,
+        FetchMut<'a, Grid>,
+        FetchMut<'a, Events>,
+
+        // Components
+        WriteStorage<'a, Expiry>,
+        ReadStorage<'a, Tile>,
+    );
+    fn run(&mut self, (entities, tick, mut grid, mut events, mut expiries, tiles): Self::SystemData) {
+        if !tick.ticked {
+            return;
+        }
+
+        for (food, expiry, tile) in (&*entities, &mut expiries, &tiles).join() {
+            // Food eaten this tick is still around until the world is maintained, but has already
+            // been taken off the grid, and shouldn't expire as well.
+            if !grid.holds(Layer::Item, tile.x as usize, tile.y as usize, food) {
+                continue;
+            }
+
+            expiry.remaining = expiry.remaining.saturating_sub(1);
+            if expiry.remaining == 0 {
+                grid.remove(tile.x as usize, tile.y as usize, food);
+                entities.delete(food);
+                events.publish(GameEvent::FoodExpired { food: food, tile: tile.clone() });
+            }
+        }
+    }
+}
+
+// Moves live food around the board, a tile at a time like the snakes but only onto empty tiles.
+#[derive(Default)]
+pub struct LiveFoodSystem;
+impl<'a> System<'a> for LiveFoodSystem {
+    type SystemData = (
+        // Resources
+        Entities<'a>,
+        Fetch<'a, Tick>,
+        FetchMut<'a, Grid>,
+        FetchMut<'a, Random>,
+        FetchMut<'a, Events>,
+
+        // Components
+        ReadStorage<'a, Live>,
+        ReadStorage<'a, Snake>,
+        WriteStorage<'a, Tile>,
+        WriteStorage<'a, Direction>,
+    );
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, tick, mut grid, mut random, mut events, lives, snakes, mut tiles, mut directions) = data;
+
+        if !tick.ticked {
+            return;
+        }
+
+        let heads = (&snakes, &tiles).join().map(|(_, tile)| tile.clone()).collect::<Vec<_>>();
+        let nearest = |x: i32, y: i32| heads.iter()
+            .map(|head| (head.x - x).abs() + (head.y - y).abs())
+            .min()
+            .unwrap_or(i32::max_value());
+
+        let moving = tick.ticks % LIVE_INTERVAL == 0;
+        for (food, live, tile, direction) in (&*entities, &lives, &mut tiles, &mut directions).join() {
+            // Food only has a direction on ticks it moves, so remember the way it was going.
+            let heading = match direction.direction {
+                (0, 0) => direction.previous.unwrap_or((0, 0)),
+                moved => moved,
+            };
+            direction.previous = Some(heading);
+            direction.direction = (0, 0);
+            if !moving {
+                continue;
+            }
+
+            // Food eaten this tick is still around until the world is maintained, but has already
+            // been taken off the grid.
+            if !grid.holds(Layer::Item, tile.x as usize, tile.y as usize, food) {
+                continue;
+            }
+
+            let free = grid.neighbors(tile.x as usize, tile.y as usize).into_iter()
+                .filter(|&(x, y)| grid.is_free(x, y))
+                .map(|(x, y)| (x as i32 - tile.x, y as i32 - tile.y))
+                .collect::<Vec<(i32, i32)>>();
+
+            let danger = nearest(tile.x, tile.y);
+            let step = if *live == Live::Flee && danger <= FLEE_RANGE {
+                // Get as far from the nearest head as possible, staying put if nowhere is further.
+                free.iter()
+                    .map(|&step| (nearest(tile.x + step.0, tile.y + step.1), step))
+                    .filter(|&(distance, _)| distance > danger)
+                    .max_by_key(|&(distance, _)| distance)
+                    .map(|(_, step)| step)
+            }
+            else {
+                // Keep going the same way until blocked or taking a random turn.
+                let ahead = (heading.0 as i32, heading.1 as i32);
+                if free.contains(&ahead) && !random.gen_weighted_bool(WANDER_TURN) {
+                    Some(ahead)
+                }
+                else {
+                    random.choose(&free).cloned()
+                }
+            };
+
+            if let Some((dx, dy)) = step {
+                let from = tile.clone();
+                grid.remove(tile.x as usize, tile.y as usize, food);
+                tile.x += dx;
+                tile.y += dy;
+                grid.insert(tile.x as usize, tile.y as usize, food, Type::Food);
+                direction.direction = (dx as i8, dy as i8);
+                events.publish(GameEvent::FoodMoved { food: food, from: from, to: tile.clone() });
+            }
+        }
+    }
+}
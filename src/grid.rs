@@ -0,0 +1,184 @@
+
+use specs::Entity;
+
+use components::Type;
+
+// Layers of a single cell, from bottom to top.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Layer {
+    // Static pieces of the board (walls, obstacles).
+    Terrain,
+
+    // Things that can be picked up (food, power-ups).
+    Item,
+
+    // Things that move around (snake segments).
+    Actor,
+}
+
+// An entity sitting in a cell along with what kind of thing it is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Occupant {
+    pub entity: Entity,
+    pub kind: Type,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Cell {
+    pub terrain: Option<Occupant>,
+    pub item: Option<Occupant>,
+    pub actor: Option<Occupant>,
+}
+impl Cell {
+    pub fn layer(&self, layer: Layer) -> Option<Occupant> {
+        match layer {
+            Layer::Terrain => self.terrain,
+            Layer::Item => self.item,
+            Layer::Actor => self.actor,
+        }
+    }
+
+    fn layer_mut(&mut self, layer: Layer) -> &mut Option<Occupant> {
+        match layer {
+            Layer::Terrain => &mut self.terrain,
+            Layer::Item => &mut self.item,
+            Layer::Actor => &mut self.actor,
+        }
+    }
+
+    // The occupant on the highest layer, which is what a snake running into this cell hits first.
+    pub fn top(&self) -> Option<Occupant> {
+        self.actor.or(self.item).or(self.terrain)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.top().is_none()
+    }
+}
+
+// Grid of everything placed on the board, split into layers so items and actors can share a cell.
+pub struct Grid {
+    cells: Vec<Cell>,
+    pub len: (usize, usize),
+}
+impl Grid {
+    pub fn new(x: usize, y: usize) -> Self {
+        Grid {
+            cells: vec![Cell::default(); x * y],
+            len: (x, y),
+        }
+    }
+
+    pub fn bounds(&self, x: usize, y: usize) -> bool {
+        x < self.len.0 && y < self.len.1
+    }
+
+    fn index(&self, x: usize, y: usize) -> usize {
+        x + (y * self.len.0)
+    }
+
+    pub fn cell(&self, x: usize, y: usize) -> Option<&Cell> {
+        if self.bounds(x, y) {
+            Some(&self.cells[self.index(x, y)])
+        }
+        else {
+            None
+        }
+    }
+
+    pub fn get(&self, layer: Layer, x: usize, y: usize) -> Option<Occupant> {
+        self.cell(x, y).and_then(|cell| cell.layer(layer))
+    }
+
+    // Whether an entity is on a layer at a position. Entities taken off the grid this tick, like
+    // food that was eaten, are still around until the world is maintained but aren't held here.
+    pub fn holds(&self, layer: Layer, x: usize, y: usize, entity: Entity) -> bool {
+        self.get(layer, x, y).map(|occupant| occupant.entity) == Some(entity)
+    }
+
+    // Place an entity on the layer its kind belongs to, returning whatever was there before.
+    pub fn insert(&mut self, x: usize, y: usize, entity: Entity, kind: Type) -> Option<Occupant> {
+        if self.bounds(x, y) {
+            let index = self.index(x, y);
+            let slot = self.cells[index].layer_mut(kind.layer());
+            ::std::mem::replace(slot, Some(Occupant { entity: entity, kind: kind }))
+        }
+        else {
+            None
+        }
+    }
+
+    // Remove an entity from a cell, leaving the cell alone if something else has since taken its place.
+    pub fn remove(&mut self, x: usize, y: usize, entity: Entity) {
+        if self.bounds(x, y) {
+            let index = self.index(x, y);
+            let cell = &mut self.cells[index];
+            for layer in &[Layer::Terrain, Layer::Item, Layer::Actor] {
+                let slot = cell.layer_mut(*layer);
+                if slot.map(|occupant| occupant.entity) == Some(entity) {
+                    *slot = None;
+                }
+            }
+        }
+    }
+
+    // What a snake would run into at this position, if anything.
+    pub fn occupant_kind(&self, x: usize, y: usize) -> Option<Type> {
+        self.cell(x, y).and_then(|cell| cell.top()).map(|occupant| occupant.kind)
+    }
+
+    // Whether the position is on the board and nothing occupies any of its layers.
+    pub fn is_free(&self, x: usize, y: usize) -> bool {
+        self.cell(x, y).map(|cell| cell.is_empty()).unwrap_or(false)
+    }
+
+    // Every position nothing occupies, column by column.
+    pub fn free(&self) -> Vec<(usize, usize)> {
+        let mut free = Vec::new();
+        for x in 0..self.len.0 {
+            for y in 0..self.len.1 {
+                if self.is_free(x, y) {
+                    free.push((x, y));
+                }
+            }
+        }
+        free
+    }
+
+    // Orthogonal neighbors of a position that are still on the board.
+    pub fn neighbors(&self, x: usize, y: usize) -> Vec<(usize, usize)> {
+        let mut neighbors = Vec::with_capacity(4);
+        if x > 0 {
+            neighbors.push((x - 1, y));
+        }
+        if y > 0 {
+            neighbors.push((x, y - 1));
+        }
+        if x + 1 < self.len.0 {
+            neighbors.push((x + 1, y));
+        }
+        if y + 1 < self.len.1 {
+            neighbors.push((x, y + 1));
+        }
+        neighbors
+    }
+
+    pub fn print(&self) {
+        for y in 0..self.len.1 {
+            for x in 0..self.len.0 {
+                match self.occupant_kind(x, y) {
+                    Some(Type::Snake) => print!("S "),
+                    Some(Type::Food) => print!("F "),
+                    Some(Type::Wall) => print!("# "),
+                    Some(Type::PowerUp(_)) => print!("P "),
+                    Some(Type::Portal { .. }) => print!("O "),
+                    Some(Type::Gate(_)) => print!("G "),
+                    Some(Type::Ice) => print!("I "),
+                    Some(Type::Conveyor(_)) => print!("C "),
+                    None => print!(". "),
+                }
+            }
+            println!();
+        }
+    }
+}
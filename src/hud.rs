@@ -0,0 +1,229 @@
+
+// Heads-up display with each player's score, length and powers, the game mode, time left and
+// arena countdown, the game speed, play time and frame rate.
+//
+// Text is drawn with a tiny bitmap font, one square renderable for every lit pixel, in screen
+// coordinates on top of the board.
+
+use amethyst::asset_manager::AssetManager;
+use amethyst::ecs::components::{LocalTransform, Transform};
+use specs::{Entity, Join, World};
+
+use arena::{self, Arena};
+use components::{Player, Powers, Snake, Tick};
+use grid::Grid;
+use mode::{self, GameMode};
+use tick::{Clock, TICK_RATE};
+
+// Size of one font pixel on screen and the gap between the HUD and the window's corner.
+pub const PIXEL: f32 = 4.0;
+pub const MARGIN: f32 = 8.0;
+
+pub const GLYPH_WIDTH: usize = 3;
+pub const GLYPH_HEIGHT: usize = 5;
+
+// Depth the HUD is drawn at, in front of the board.
+const DEPTH: f32 = 0.5;
+
+// Rows of a glyph from top to bottom, the highest of the three bits being the leftmost pixel.
+pub fn glyph(c: char) -> [u8; GLYPH_HEIGHT] {
+    match c.to_uppercase().next().unwrap_or(c) {
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+        '3' => [0b111, 0b001, 0b111, 0b001, 0b111],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b010, 0b010, 0b010],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+        'A' => [0b010, 0b101, 0b111, 0b101, 0b101],
+        'B' => [0b110, 0b101, 0b110, 0b101, 0b110],
+        'C' => [0b011, 0b100, 0b100, 0b100, 0b011],
+        'D' => [0b110, 0b101, 0b101, 0b101, 0b110],
+        'E' => [0b111, 0b100, 0b110, 0b100, 0b111],
+        'F' => [0b111, 0b100, 0b110, 0b100, 0b100],
+        'G' => [0b011, 0b100, 0b101, 0b101, 0b011],
+        'H' => [0b101, 0b101, 0b111, 0b101, 0b101],
+        'I' => [0b111, 0b010, 0b010, 0b010, 0b111],
+        'J' => [0b001, 0b001, 0b001, 0b101, 0b010],
+        'K' => [0b101, 0b101, 0b110, 0b101, 0b101],
+        'L' => [0b100, 0b100, 0b100, 0b100, 0b111],
+        'M' => [0b101, 0b111, 0b111, 0b101, 0b101],
+        'N' => [0b110, 0b101, 0b101, 0b101, 0b101],
+        'O' => [0b010, 0b101, 0b101, 0b101, 0b010],
+        'P' => [0b110, 0b101, 0b110, 0b100, 0b100],
+        'Q' => [0b010, 0b101, 0b101, 0b110, 0b011],
+        'R' => [0b110, 0b101, 0b110, 0b101, 0b101],
+        'S' => [0b011, 0b100, 0b010, 0b001, 0b110],
+        'T' => [0b111, 0b010, 0b010, 0b010, 0b010],
+        'U' => [0b101, 0b101, 0b101, 0b101, 0b111],
+        'V' => [0b101, 0b101, 0b101, 0b101, 0b010],
+        'W' => [0b101, 0b101, 0b111, 0b111, 0b101],
+        'X' => [0b101, 0b101, 0b010, 0b101, 0b101],
+        'Y' => [0b101, 0b101, 0b010, 0b010, 0b010],
+        'Z' => [0b111, 0b001, 0b010, 0b100, 0b111],
+        ':' => [0b000, 0b010, 0b000, 0b010, 0b000],
+        '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+        '/' => [0b001, 0b001, 0b010, 0b100, 0b100],
+        '-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+        _ => [0; GLYPH_HEIGHT],
+    }
+}
+
+// Lit pixels of the text on a line, counting from the top line, as (column, row) with one blank
+// column between characters and two blank rows between lines.
+pub fn pixels(line: usize, text: &str) -> Vec<(usize, usize)> {
+    let mut pixels = Vec::new();
+    for (column, c) in text.chars().enumerate() {
+        for (row, bits) in glyph(c).iter().enumerate() {
+            for x in 0..GLYPH_WIDTH {
+                if bits & (1 << (GLYPH_WIDTH - 1 - x)) != 0 {
+                    pixels.push((column * (GLYPH_WIDTH + 1) + x, line * (GLYPH_HEIGHT + 2) + row));
+                }
+            }
+        }
+    }
+    pixels
+}
+
+// The text shown on the HUD.
+pub fn lines(world: &World) -> Vec<String> {
+    let snakes = world.read::<Snake>();
+    let players = world.read::<Player>();
+    let clock = world.read_resource::<Clock>();
+
+    let powers = world.read::<Powers>();
+
+    let mut scores = (&*world.entities(), &snakes, &players).join()
+        .map(|(entity, snake, player)| (player.0, snake.score, snake.length, powers.get(entity).cloned().unwrap_or_default()))
+        .collect::<Vec<_>>();
+    scores.sort_by_key(|&(player, _, _, _)| player);
+
+    let mut lines = scores.into_iter()
+        .map(|(player, score, length, powers)| {
+            let mut line = format!("P{} SCORE {} LENGTH {}", player + 1, score, length);
+            for (power, remaining) in powers.0 {
+                line.push_str(&format!(" {} {}", power.name(), remaining));
+            }
+            line.to_uppercase()
+        })
+        .collect::<Vec<_>>();
+
+    let mode = *world.read_resource::<GameMode>();
+    lines.push(format!("MODE {}", mode.name().replace('_', " ").to_uppercase()));
+    if let Some(ticks) = mode::remaining(mode, &world.read_resource::<Tick>()) {
+        let seconds = ticks * TICK_RATE / 1_000_000_000;
+        lines.push(format!("LEFT {}:{:02}", seconds / 60, seconds % 60));
+    }
+
+    if let Some(ticks) = world.read_resource::<Arena>().closing_in(&world.read_resource::<Tick>(), world.read_resource::<Grid>().len) {
+        if ticks <= arena::WARNING {
+            lines.push(format!("ARENA CLOSING {}", ticks));
+        }
+    }
+
+    let seconds = clock.elapsed.as_secs();
+    lines.push(format!("SPEED {:.1} TICKS/S", clock.ticks_per_second));
+    lines.push(format!("TIME {}:{:02}", seconds / 60, seconds % 60));
+    lines.push(format!("FPS {:.0}", clock.shown_fps));
+    lines
+}
+
+pub struct Hud {
+    pub visible: bool,
+
+    // What was drawn last and in which colour, with the pixels lighting up each line, so only the
+    // lines whose text changes have their pixels moved.
+    drawn: Vec<String>,
+    colour: String,
+    pixels: Vec<Vec<Entity>>,
+}
+impl Default for Hud {
+    fn default() -> Self {
+        Hud {
+            visible: true,
+            drawn: Vec::new(),
+            colour: String::new(),
+            pixels: Vec::new(),
+        }
+    }
+}
+impl Hud {
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    // Bring the HUD's pixels up to date with the game, drawn with the texture called `colour`.
+    pub fn draw(&mut self, world: &mut World, assets: &mut AssetManager, colour: &str) {
+        // Restarting the game deletes every entity, pixels included.
+        {
+            let entities = world.entities();
+            let alive = self.pixels.iter().flat_map(|line| line.iter()).all(|&pixel| entities.is_alive(pixel));
+            if !alive {
+                self.pixels.clear();
+                self.drawn.clear();
+            }
+        }
+
+        // Pixels in the old colour are thrown away and made again.
+        if self.colour != colour {
+            for pixel in self.pixels.drain(..).flat_map(|line| line.into_iter()) {
+                world.entities().delete(pixel);
+            }
+            self.drawn.clear();
+            self.colour = colour.to_owned();
+        }
+
+        let lines = if self.visible { lines(world) } else { Vec::new() };
+        if lines == self.drawn {
+            return;
+        }
+
+        while self.pixels.len() > lines.len() {
+            for pixel in self.pixels.pop().unwrap() {
+                world.entities().delete(pixel);
+            }
+        }
+
+        for (index, text) in lines.iter().enumerate() {
+            if self.drawn.get(index) == Some(text) {
+                continue;
+            }
+            if self.pixels.len() <= index {
+                self.pixels.push(Vec::new());
+            }
+
+            let line = &mut self.pixels[index];
+            let lit = pixels(index, text);
+            while line.len() > lit.len() {
+                let pixel = line.pop().unwrap();
+                world.entities().delete(pixel);
+            }
+            while line.len() < lit.len() {
+                let renderable = assets.create_renderable("square", colour, colour, colour, 1.0).unwrap();
+                let pixel = world.create_entity()
+                    .with(renderable)
+                    .with(LocalTransform::default())
+                    .with(Transform::default())
+                    .build();
+                line.push(pixel);
+            }
+
+            let mut locals = world.write::<LocalTransform>();
+            for (&pixel, &(x, y)) in line.iter().zip(&lit) {
+                if let Some(local) = locals.get_mut(pixel) {
+                    local.translation = [
+                        MARGIN + (x as f32 + 0.5) * PIXEL,
+                        MARGIN + (y as f32 + 0.5) * PIXEL,
+                        DEPTH,
+                    ];
+                    local.scale = [PIXEL, PIXEL, 1.0];
+                }
+            }
+        }
+
+        self.drawn = lines;
+    }
+}
@@ -0,0 +1,47 @@
+
+use amethyst::ecs::resources::InputHandler;
+use specs::{Entities, Fetch, FetchMut, Join, System, ReadStorage, WriteStorage};
+
+use components::{Controls, Direction, Snake};
+use event::{Events, GameEvent};
+
+// Reads the keyboard and queues directions for the snakes being controlled by it.
+#[derive(Default)]
+pub struct InputSystem;
+impl<'a> System<'a> for InputSystem {
+    type SystemData = (
+        // Resources
+        Entities<'a>,
+        Fetch<'a, InputHandler>,
+        FetchMut<'a, Events>,
+
+        // Components
+        WriteStorage<'a, Snake>,
+        ReadStorage<'a, Direction>,
+        ReadStorage<'a, Controls>,
+    );
+    fn run(&mut self, (entities, input, mut events, mut snakes, directions, controls): Self::SystemData) {
+        // Iterate over snakes.
+        for (entity, direction, snake, controls) in (&*entities, &directions, &mut snakes, &controls).join() {
+            let pressed = [
+                (controls.left, (-1, 0)),
+                (controls.right, (1, 0)),
+                (controls.up, (0, -1)),
+                (controls.down, (0, 1)),
+            ];
+
+            // Queue the first valid direction for the next tick.
+            let queued = snake.queued_direction;
+            for &(button, wanted) in &pressed {
+                if input.button_down(button) && snake.steer(direction, wanted) {
+                    break;
+                }
+            }
+
+            // Keys are held down for many frames, only count it once.
+            if snake.queued_direction != queued {
+                events.publish(GameEvent::SnakeSteered { snake: entity, direction: snake.queued_direction });
+            }
+        }
+    }
+}
@@ -0,0 +1,144 @@
+
+// Levels, loaded from the files in `resources/levels`, with the walls and other terrain placed at the
+// start of every game and the zones food is spawned in.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, ErrorKind, Read};
+use std::path::{Path, PathBuf};
+
+use serde_yaml;
+use specs::World;
+
+use components::{Tile, Type};
+use grid::Grid;
+
+// Directory levels are kept in.
+pub const LEVEL_DIR: &'static str = "resources/levels";
+
+// A rectangle of the board food can be spawned in, picked more often the heavier it is.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Zone {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+    pub weight: u32,
+}
+impl Zone {
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        x >= self.x && x < self.x + self.width && y >= self.y && y < self.y + self.height
+    }
+}
+
+// An empty board unless loaded from a file.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Level {
+    pub name: String,
+
+    #[serde(default)]
+    pub walls: Vec<(usize, usize)>,
+
+    #[serde(default)]
+    pub zones: Vec<Zone>,
+
+    // Pairs of portals, a snake going into either end comes out of the other.
+    #[serde(default)]
+    pub portals: Vec<((usize, usize), (usize, usize))>,
+
+    // One-way gates with the way through them, ice, and conveyors with the way they carry snakes.
+    #[serde(default)]
+    pub gates: Vec<((usize, usize), (i8, i8))>,
+    #[serde(default)]
+    pub ice: Vec<(usize, usize)>,
+    #[serde(default)]
+    pub conveyors: Vec<((usize, usize), (i8, i8))>,
+}
+impl Level {
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Level> {
+        let mut text = String::new();
+        File::open(path)?.read_to_string(&mut text)?;
+        Level::parse(&text)
+    }
+
+    // A level from the text of a level file, which can't have two kinds of terrain on one tile.
+    pub fn parse(text: &str) -> io::Result<Level> {
+        let level: Level = serde_yaml::from_str(text)
+            .map_err(|error| io::Error::new(ErrorKind::InvalidData, error))?;
+
+        let tiles = level.walls.iter().map(|&tile| (tile, "wall"))
+            .chain(level.portals.iter().flat_map(|&(a, b)| vec![(a, "portal"), (b, "portal")]))
+            .chain(level.gates.iter().map(|&(tile, _)| (tile, "gate")))
+            .chain(level.ice.iter().map(|&tile| (tile, "ice")))
+            .chain(level.conveyors.iter().map(|&(tile, _)| (tile, "conveyor")));
+        let mut taken = HashMap::new();
+        for ((x, y), kind) in tiles {
+            if let Some(first) = taken.insert((x, y), kind) {
+                let message = format!("level {:?} has a {} and a {} on tile ({}, {})", level.name, first, kind, x, y);
+                return Err(io::Error::new(ErrorKind::InvalidData, message));
+            }
+        }
+        Ok(level)
+    }
+
+    // The level with a name in the level directory.
+    pub fn named(name: &str) -> io::Result<Level> {
+        Level::load(path(name)?)
+    }
+
+    // Put the level's terrain on the board, skipping anything that doesn't fit on it or points
+    // some way other than straight up, down, left or right.
+    pub fn build(&self, world: &mut World) {
+        let terrain = self.walls.iter().map(|&tile| (tile, Type::Wall))
+            .chain(self.ice.iter().map(|&tile| (tile, Type::Ice)))
+            .chain(self.gates.iter().filter(|&&(_, way)| straight(way)).map(|&(tile, way)| (tile, Type::Gate(way))))
+            .chain(self.conveyors.iter().filter(|&&(_, way)| straight(way)).map(|&(tile, way)| (tile, Type::Conveyor(way))))
+            .collect::<Vec<_>>();
+        for ((x, y), kind) in terrain {
+            if !world.read_resource::<Grid>().bounds(x, y) {
+                continue;
+            }
+
+            let entity = world.create_entity()
+                .with(Tile { x: x as i32, y: y as i32 })
+                .with(kind)
+                .build();
+            world.write_resource::<Grid>().insert(x, y, entity, kind);
+        }
+
+        for &(a, b) in &self.portals {
+            let fits = {
+                let grid = world.read_resource::<Grid>();
+                grid.bounds(a.0, a.1) && grid.bounds(b.0, b.1) && a != b
+            };
+            if !fits {
+                continue;
+            }
+
+            for &((x, y), exit) in &[(a, b), (b, a)] {
+                let kind = Type::Portal { x: exit.0, y: exit.1 };
+                let portal = world.create_entity()
+                    .with(Tile { x: x as i32, y: y as i32 })
+                    .with(kind)
+                    .build();
+                world.write_resource::<Grid>().insert(x, y, portal, kind);
+            }
+        }
+    }
+}
+
+// Whether a way is one step in one of the four directions.
+fn straight(way: (i8, i8)) -> bool {
+    (way.0 == 0) != (way.1 == 0) && way.0.abs() <= 1 && way.1.abs() <= 1
+}
+
+// Where the level with a name lives. Names are plain so they can't point outside the level
+// directory.
+pub fn path(name: &str) -> io::Result<PathBuf> {
+    let valid = !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        return Err(io::Error::new(ErrorKind::InvalidInput, format!("invalid level name {:?}", name)));
+    }
+
+    Ok(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join(LEVEL_DIR).join(format!("{}.yml", name)))
+}
@@ -0,0 +1,52 @@
+
+extern crate amethyst;
+extern crate gfx;
+extern crate specs;
+extern crate rand;
+#[macro_use]
+extern crate log;
+extern crate env_logger;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+extern crate serde_yaml;
+#[cfg(feature = "sound")]
+extern crate rodio;
+
+pub mod components;
+pub mod grid;
+pub mod body;
+pub mod state;
+pub mod tile;
+pub mod input;
+pub mod controller;
+pub mod tick;
+pub mod extend;
+pub mod food;
+pub mod random;
+pub mod simulation;
+pub mod terminal;
+pub mod net;
+pub mod stream;
+pub mod snapshot;
+pub mod hud;
+pub mod sprite;
+pub mod theme;
+pub mod event;
+pub mod outcome;
+pub mod effects;
+pub mod logging;
+pub mod telemetry;
+pub mod power;
+pub mod level;
+pub mod shrink;
+pub mod mode;
+pub mod arena;
+pub mod raster;
+
+pub const GRID_X: usize = 12;
+pub const GRID_Y: usize = 12;
+
+// Number of snakes in a game, set to 2 for local multiplayer.
+pub const PLAYERS: usize = 1;
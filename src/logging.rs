@@ -0,0 +1,18 @@
+
+// Log output for the game, at info level unless `RUST_LOG` says otherwise, e.g.
+// `RUST_LOG=snake=debug` to see every food eaten.
+
+use std::env;
+
+use env_logger::LogBuilder;
+use log::LogLevelFilter;
+
+// Start logging to standard error. Only the first call does anything.
+pub fn init() {
+    let mut builder = LogBuilder::new();
+    builder.filter(None, LogLevelFilter::Info);
+    if let Ok(filters) = env::var("RUST_LOG") {
+        builder.parse(&filters);
+    }
+    let _ = builder.init();
+}
@@ -1,62 +1,102 @@
 
 extern crate amethyst;
+#[macro_use]
+extern crate log;
 extern crate specs;
-extern crate rand;
+extern crate snake;
+
+use std::env;
 
 use amethyst::Application;
-use amethyst::project::Config;
 use amethyst::ecs::World;
 use amethyst::ecs::systems::TransformSystem;
 use amethyst::gfx_device::DisplayConfig;
 
 use specs::{DispatcherBuilder};
 
-use components::*;
-use state::GameState;
-use tile::TileSystem;
-use controller::ControllerSystem;
-use tick::TickSystem;
-use extend::ExtendSystem;
+use snake::components::*;
+use snake::{logging, random, simulation};
+use snake::stream::{Stream, StreamSystem};
+use snake::telemetry::TelemetrySystem;
+use snake::state::GameState;
+use snake::effects::{Effects, FlashSystem, ParticleSystem, Shake, ShakeSystem, SoundSystem};
+use snake::sprite::SpriteSystem;
+use snake::tile::{Layout, TileSystem};
+use snake::input::InputSystem;
+use snake::level::Level;
+use snake::mode::Config;
+use snake::tick::TickSystem;
+use snake::{GRID_X, GRID_Y};
 
-mod components;
-mod state;
-mod tile;
-mod controller;
-mod tick;
-mod extend;
+fn main() {
+    logging::init();
 
-pub const TILE_SIZE: f32 = 100.0;
-pub const GRID_X: usize = 12; // WINDOW_SIZE / (TILE_SIZE / 2)
-pub const GRID_Y: usize = 12;
+    // Optionally stream the game to spectators with `--stream <target>`.
+    let stream = env::args().skip_while(|arg| arg != "--stream").nth(1)
+        .map(|target| Stream::open(&target).expect("failed to open the stream"));
+
+    // Slide the snakes smoothly between tiles with `--smooth`.
+    let smooth = env::args().any(|arg| arg == "--smooth");
+
+    // Start with one of the themes in `resources/themes` with `--theme <name>`.
+    let theme = env::args().skip_while(|arg| arg != "--theme").nth(1);
+
+    // Every game played is recorded in `telemetry.jsonl`, or somewhere else with `--telemetry <path>`.
+    let telemetry = env::args().skip_while(|arg| arg != "--telemetry").nth(1)
+        .unwrap_or_else(|| format!("{}/telemetry.jsonl", env!("CARGO_MANIFEST_DIR")));
+    let telemetry = TelemetrySystem::open(&telemetry).unwrap_or_else(|error| {
+        warn!("Not recording telemetry to {}: {}", telemetry, error);
+        TelemetrySystem::default()
+    });
+
+    // Play on one of the levels in `resources/levels` with `--level <name>`.
+    let level = env::args().skip_while(|arg| arg != "--level").nth(1)
+        .map(|name| Level::named(&name).expect("failed to load the level"))
+        .unwrap_or_default();
+
+    // Play the mode in `resources/game.yml`, or another one with `--mode <name>`.
+    let mode = Config::read().mode(env::args().skip_while(|arg| arg != "--mode").nth(1))
+        .expect("failed to pick the game mode");
 
-fn main() {
     let path = format!("{}/resources/config.yml", env!("CARGO_MANIFEST_DIR"));
     let cfg = DisplayConfig::load(path);
     
     let mut world = World::new();
-    // Add resources.
-    world.add_resource::<Tick>(Tick::default());
-    world.add_resource::<Grid>(Grid::new(GRID_X, GRID_Y));
+    // Add resources and register components used by the game rules.
+    simulation::register(&mut world, GRID_X, GRID_Y, random::seed());
+    world.add_resource(level);
+    world.add_resource(mode);
 
-    // Register component.
+    // Register window only components and resources.
     world.register::<Controls>();
-    world.register::<Snake>();
-    world.register::<Type>();
-    world.register::<Parent>();
-    world.register::<Direction>();
-    world.register::<Extension>();
-    world.register::<Tile>();
+    world.register::<Board>();
+    world.register::<GridLine>();
+    world.register::<Particle>();
+    world.register::<Flash>();
+    world.add_resource(Effects::default());
+    world.add_resource(Shake::default());
+    world.register::<Sprite>();
+    world.register::<Appearance>();
+    world.add_resource(Layout::default());
 
     // Add systems that should be run in parallel.
     let dispatcher = DispatcherBuilder::new()
-        .add(ControllerSystem::default(), "controller", &[])
-        .add(TickSystem::default(), "ticks", &["controller"])
-        .add(TileSystem(0.0), "tiles", &[])
-        .add(TransformSystem::new(), "transform", &["tiles"])
-        .add(ExtendSystem::default(), "extend", &["tiles", "ticks"])
+        .add(InputSystem::default(), "input", &[])
+        .add(TickSystem::default(), "ticks", &["input"]);
+
+    // The game rules run once the tick for this frame is known.
+    let dispatcher = simulation::rules(dispatcher, &["ticks"])
+        .add(TileSystem { smooth: smooth }, "tiles", &["food"])
+        .add(SpriteSystem::default(), "sprites", &["food"])
+        .add(ParticleSystem::default(), "particles", &["tiles"])
+        .add(TransformSystem::new(), "transform", &["tiles", "sprites", "particles"])
+        .add(StreamSystem(stream), "stream", &["food"])
+        .add(ShakeSystem::default(), "shake", &["tiles"])
+        .add(FlashSystem::default(), "flash", &["food"])
+        .add(SoundSystem::default(), "sound", &["food"])
+        .add(telemetry, "telemetry", &["food"])
         .build();
 
-    let mut game = Application::new(GameState, dispatcher, world, cfg);
+    let mut game = Application::new(GameState::new(theme), dispatcher, world, cfg);
     game.run();
 }
-
@@ -0,0 +1,210 @@
+
+// Game modes, each with its own rules for when the game ends, how food is spawned and how snakes
+// score. The mode is a resource the rule systems check, and it sets the spawn rules, decay and arena
+// at the start of every game.
+
+use std::fs::File;
+use std::io::{self, ErrorKind};
+use std::path::Path;
+
+use serde_yaml;
+use specs::{Fetch, FetchMut, Join, System, World, WriteStorage};
+
+use components::{Snake, Tick};
+use arena::Arena;
+use event::{Events, GameEvent};
+use food::{SpawnRules, When, Where};
+use shrink::Decay;
+use tick::TICK_RATE;
+
+// How long a time attack lasts, in ticks at normal speed.
+pub const TIME_LIMIT: u64 = 120 * 1_000_000_000 / TICK_RATE;
+
+// How many ticks a snake has to stay alive in survival for each point.
+pub const SURVIVAL_POINTS: u64 = 10;
+
+// How many ticks between snakes losing a segment in survival.
+pub const SURVIVAL_DECAY: u64 = 40;
+
+// How many ticks between the arena closing in for survival and battle royale.
+pub const SURVIVAL_ARENA: u64 = 200;
+pub const BATTLE_ARENA: u64 = 150;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GameMode {
+    // Grow until dying, the game ends at the first death.
+    Classic,
+
+    // Score as much as possible before the time runs out.
+    TimeAttack,
+
+    // Stay alive as long as possible while the snakes slowly shrink and the arena closes in.
+    Survival,
+
+    // Snakes that die are taken off the board as the arena closes in, the last one standing wins.
+    BattleRoyale,
+}
+impl Default for GameMode {
+    fn default() -> Self {
+        GameMode::Classic
+    }
+}
+
+pub const MODES: [GameMode; 4] = [GameMode::Classic, GameMode::TimeAttack, GameMode::Survival, GameMode::BattleRoyale];
+
+impl GameMode {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            GameMode::Classic => "classic",
+            GameMode::TimeAttack => "time_attack",
+            GameMode::Survival => "survival",
+            GameMode::BattleRoyale => "battle_royale",
+        }
+    }
+
+    pub fn named(name: &str) -> Option<GameMode> {
+        MODES.iter().find(|mode| mode.name() == name).cloned()
+    }
+
+    // The mode after this one, for cycling through them.
+    pub fn next(&self) -> GameMode {
+        let index = MODES.iter().position(|mode| mode == self).unwrap_or(0);
+        MODES[(index + 1) % MODES.len()]
+    }
+
+    // How many ticks the game lasts, if it has a time limit.
+    pub fn time_limit(&self) -> Option<u64> {
+        match *self {
+            GameMode::TimeAttack => Some(TIME_LIMIT),
+            _ => None,
+        }
+    }
+
+    // Whether snakes that die leave the board while the rest carry on.
+    pub fn last_standing(&self) -> bool {
+        match *self {
+            GameMode::Survival | GameMode::BattleRoyale => true,
+            GameMode::Classic | GameMode::TimeAttack => false,
+        }
+    }
+
+    pub fn spawn_rules(&self) -> SpawnRules {
+        match *self {
+            GameMode::Classic => SpawnRules::default(),
+            // Plenty of food so there is always something to go for.
+            GameMode::TimeAttack => SpawnRules {
+                when: When::Keep(3),
+                place: Where::Anywhere,
+                expiring: 2,
+                live: 3,
+                poison: 6,
+            },
+            // Scarce food that has to be gone out of the way for.
+            GameMode::Survival => SpawnRules {
+                when: When::Interval { ticks: 80, max: 2 },
+                place: Where::AwayFromHeads(4),
+                .. SpawnRules::default()
+            },
+            GameMode::BattleRoyale => SpawnRules {
+                when: When::Keep(4),
+                .. SpawnRules::default()
+            },
+        }
+    }
+
+    pub fn decay(&self) -> Decay {
+        match *self {
+            GameMode::Survival => Decay { every: SURVIVAL_DECAY },
+            _ => Decay::default(),
+        }
+    }
+
+    pub fn arena(&self) -> Arena {
+        let every = match *self {
+            GameMode::Survival => SURVIVAL_ARENA,
+            GameMode::BattleRoyale => BATTLE_ARENA,
+            GameMode::Classic | GameMode::TimeAttack => 0,
+        };
+        Arena { every: every, closed: 0 }
+    }
+
+    // Set up the spawn rules, decay and arena for a new game in this mode.
+    pub fn apply(&self, world: &mut World) {
+        *world.write_resource::<SpawnRules>() = self.spawn_rules();
+        *world.write_resource::<Decay>() = self.decay();
+        *world.write_resource::<Arena>() = self.arena();
+    }
+}
+
+// Settings for the game from `resources/game.yml`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Config {
+    #[serde(default)]
+    pub mode: GameMode,
+}
+impl Config {
+    // The settings in `resources/game.yml`, or the defaults if they can't be read.
+    pub fn read() -> Config {
+        let path = format!("{}/resources/game.yml", env!("CARGO_MANIFEST_DIR"));
+        Config::load(&path).unwrap_or_else(|error| {
+            warn!("Using the default settings, couldn't read {}: {}", path, error);
+            Config::default()
+        })
+    }
+
+    // The mode to play, `--mode <name>` on the command line if given.
+    pub fn mode(&self, name: Option<String>) -> io::Result<GameMode> {
+        let name = match name {
+            Some(name) => name,
+            None => return Ok(self.mode),
+        };
+
+        GameMode::named(&name).ok_or_else(|| {
+            let modes = MODES.iter().map(|mode| mode.name()).collect::<Vec<_>>().join(", ");
+            io::Error::new(ErrorKind::InvalidInput, format!("no game mode called {:?}, try one of {}", name, modes))
+        })
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Config> {
+        let file = File::open(path)?;
+        serde_yaml::from_reader(file).map_err(|error| io::Error::new(ErrorKind::InvalidData, error))
+    }
+}
+
+// Ends the game when its time runs out and scores snakes for staying alive.
+#[derive(Default)]
+pub struct ModeSystem;
+impl<'a> System<'a> for ModeSystem {
+    type SystemData = (
+        // Resources
+        Fetch<'a, GameMode>,
+        FetchMut<'a, Tick>,
+        FetchMut<'a, Events>,
+
+        // Components
+        WriteStorage<'a, Snake>,
+    );
+    fn run(&mut self, (mode, mut tick, mut events, mut snakes): Self::SystemData) {
+        if !tick.ticked || tick.game_over {
+            return;
+        }
+
+        if *mode == GameMode::Survival && tick.ticks % SURVIVAL_POINTS == 0 {
+            for snake in (&mut snakes).join() {
+                snake.score += 1;
+            }
+        }
+
+        if mode.time_limit().map(|limit| tick.ticks >= limit).unwrap_or(false) {
+            info!("Time's up after {} ticks", tick.ticks);
+            tick.game_over = true;
+            events.publish(GameEvent::GameOver);
+        }
+    }
+}
+
+// Ticks left before the game's time runs out, if it has a time limit.
+pub fn remaining(mode: GameMode, tick: &Tick) -> Option<u64> {
+    mode.time_limit().map(|limit| limit.saturating_sub(tick.ticks))
+}
@@ -0,0 +1,360 @@
+
+// Networked multiplayer in lockstep.
+//
+// The server owns the clock. Clients send the directions their player presses, and at every tick
+// the server broadcasts the inputs it collected for that tick. Every peer, the server included,
+// applies those inputs to a simulation seeded the same way and steps it once, so all of them see
+// the same grid. Each tick message carries the server's checksum of the board after the tick so
+// clients notice if they ever drift apart.
+//
+// Messages are single lines of text:
+//
+//   welcome <player> <players> <seed> <grid x> <grid y>    server -> client, once connected
+//   input <tick> <dx> <dy>                                 client -> server
+//   restart                                                client -> server
+//   tick <tick> <checksum> [<player>,<dx>,<dy> ...]        server -> client
+//   reset                                                  server -> client
+
+use std::io::{self, BufRead, BufReader, ErrorKind, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::str::FromStr;
+use std::thread;
+use std::time::Duration;
+
+use components::Tick;
+use simulation::Simulation;
+use tick;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Message {
+    Welcome {
+        player: usize,
+        players: usize,
+        seed: u64,
+        grid: (usize, usize),
+    },
+    Input {
+        tick: u64,
+        direction: (i8, i8),
+    },
+    Restart,
+    Tick {
+        tick: u64,
+        checksum: u64,
+        inputs: Vec<(usize, (i8, i8))>,
+    },
+    Reset,
+}
+impl Message {
+    pub fn encode(&self) -> String {
+        match *self {
+            Message::Welcome { player, players, seed, grid } =>
+                format!("welcome {} {} {} {} {}", player, players, seed, grid.0, grid.1),
+            Message::Input { tick, direction } =>
+                format!("input {} {} {}", tick, direction.0, direction.1),
+            Message::Restart => "restart".to_owned(),
+            Message::Tick { tick, checksum, ref inputs } => {
+                let mut line = format!("tick {} {}", tick, checksum);
+                for &(player, direction) in inputs {
+                    line.push_str(&format!(" {},{},{}", player, direction.0, direction.1));
+                }
+                line
+            },
+            Message::Reset => "reset".to_owned(),
+        }
+    }
+
+    pub fn decode(line: &str) -> Option<Message> {
+        Message::parse(line).ok()
+    }
+
+    fn parse(line: &str) -> Result<Message, ()> {
+        let mut words = line.split_whitespace();
+        let message = match words.next() {
+            Some("welcome") => Message::Welcome {
+                player: parse(words.next())?,
+                players: parse(words.next())?,
+                seed: parse(words.next())?,
+                grid: (parse(words.next())?, parse(words.next())?),
+            },
+            Some("input") => Message::Input {
+                tick: parse(words.next())?,
+                direction: (parse(words.next())?, parse(words.next())?),
+            },
+            Some("restart") => Message::Restart,
+            Some("tick") => Message::Tick {
+                tick: parse(words.next())?,
+                checksum: parse(words.next())?,
+                inputs: words.map(|input| {
+                    let mut parts = input.split(',');
+                    Ok((parse(parts.next())?, (parse(parts.next())?, parse(parts.next())?)))
+                }).collect::<Result<Vec<_>, ()>>()?,
+            },
+            Some("reset") => Message::Reset,
+            _ => return Err(()),
+        };
+
+        Ok(message)
+    }
+}
+
+fn parse<T: FromStr>(word: Option<&str>) -> Result<T, ()> {
+    word.ok_or(()).and_then(|word| word.parse().map_err(|_| ()))
+}
+
+// A line based connection to another peer.
+pub struct Connection {
+    stream: TcpStream,
+    reader: BufReader<TcpStream>,
+    pending: String,
+}
+impl Connection {
+    pub fn new(stream: TcpStream) -> io::Result<Self> {
+        stream.set_nodelay(true)?;
+        Ok(Connection {
+            reader: BufReader::new(stream.try_clone()?),
+            stream: stream,
+            pending: String::new(),
+        })
+    }
+
+    pub fn send(&mut self, message: &Message) -> io::Result<()> {
+        writeln!(self.stream, "{}", message.encode())
+    }
+
+    // Wait for the next message.
+    pub fn recv(&mut self) -> io::Result<Message> {
+        loop {
+            if let Some(message) = self.poll()? {
+                return Ok(message);
+            }
+
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    // Read the next message if a whole one has arrived, without waiting for one.
+    pub fn poll(&mut self) -> io::Result<Option<Message>> {
+        self.stream.set_nonblocking(true)?;
+        let read = self.reader.read_line(&mut self.pending);
+        self.stream.set_nonblocking(false)?;
+
+        match read {
+            Ok(0) => return Err(io::Error::new(ErrorKind::UnexpectedEof, "peer disconnected")),
+            Ok(_) => { },
+            Err(ref error) if error.kind() == ErrorKind::WouldBlock => return Ok(None),
+            Err(error) => return Err(error),
+        }
+
+        // Partial lines stay pending until the rest of the line arrives.
+        if !self.pending.ends_with('\n') {
+            return Ok(None);
+        }
+
+        let message = Message::decode(&self.pending);
+        self.pending.clear();
+        match message {
+            Some(message) => Ok(Some(message)),
+            None => Err(io::Error::new(ErrorKind::InvalidData, "malformed message")),
+        }
+    }
+}
+
+// Runs the authoritative simulation and relays inputs between clients.
+pub struct Server {
+    pub simulation: Simulation,
+
+    // The clients still connected, with the player each of them is playing.
+    clients: Vec<(usize, Connection)>,
+    inputs: Vec<(usize, (i8, i8))>,
+    accumulator: Duration,
+}
+impl Server {
+    // Wait for a client to connect for each player, then start the game.
+    pub fn host<A: ToSocketAddrs>(address: A, players: usize, grid: (usize, usize), seed: u64) -> io::Result<Self> {
+        let listener = TcpListener::bind(address)?;
+        Server::accept(&listener, players, grid, seed)
+    }
+
+    pub fn accept(listener: &TcpListener, players: usize, grid: (usize, usize), seed: u64) -> io::Result<Self> {
+        let mut clients = Vec::with_capacity(players);
+        for player in 0..players {
+            let (stream, _) = listener.accept()?;
+            let mut client = Connection::new(stream)?;
+            client.send(&Message::Welcome {
+                player: player,
+                players: players,
+                seed: seed,
+                grid: grid,
+            })?;
+            clients.push((player, client));
+        }
+
+        Ok(Server {
+            simulation: Simulation::seeded(grid.0, grid.1, players, seed),
+            clients: clients,
+            inputs: Vec::new(),
+            accumulator: Duration::new(0, 0),
+        })
+    }
+
+    // Collect inputs from clients and run a tick whenever enough time has passed.
+    pub fn update(&mut self, delta: Duration) -> io::Result<bool> {
+        self.receive()?;
+
+        self.accumulator += delta;
+        let rate = tick::rate(&self.simulation.world.read_resource::<Tick>());
+        if self.accumulator < Duration::new(0, rate as u32) {
+            return Ok(false);
+        }
+
+        self.accumulator = Duration::new(0, 0);
+        self.tick()?;
+        Ok(true)
+    }
+
+    // Run one tick with the inputs collected so far and send them to every client.
+    pub fn tick(&mut self) -> io::Result<()> {
+        let inputs = ::std::mem::replace(&mut self.inputs, Vec::new());
+        self.simulation.step_with(&inputs);
+
+        let message = Message::Tick {
+            tick: self.simulation.ticks(),
+            checksum: self.simulation.checksum(),
+            inputs: inputs,
+        };
+        self.broadcast(&message)
+    }
+
+    // Read whatever clients have sent since the last update. Clients that have gone away are
+    // dropped, their snakes carry on without them.
+    pub fn receive(&mut self) -> io::Result<()> {
+        let mut restart = false;
+        let mut gone = Vec::new();
+        for &mut (player, ref mut client) in &mut self.clients {
+            loop {
+                let message = match client.poll() {
+                    Ok(Some(message)) => message,
+                    Ok(None) => break,
+                    Err(error) => {
+                        warn!("Dropping player {}: {}", player, error);
+                        gone.push(player);
+                        break;
+                    },
+                };
+
+                match message {
+                    // Inputs made before a restart don't carry over into the new game.
+                    Message::Input { tick, direction } => if tick <= self.simulation.ticks() {
+                        self.inputs.push((player, direction));
+                    },
+                    Message::Restart => restart = true,
+                    _ => { },
+                }
+            }
+        }
+        self.clients.retain(|&(player, _)| !gone.contains(&player));
+
+        if restart {
+            self.inputs.clear();
+            self.simulation.reset();
+            self.broadcast(&Message::Reset)?;
+        }
+
+        Ok(())
+    }
+
+    // How many clients are still connected.
+    pub fn connected(&self) -> usize {
+        self.clients.len()
+    }
+
+    fn broadcast(&mut self, message: &Message) -> io::Result<()> {
+        let mut gone = Vec::new();
+        for &mut (player, ref mut client) in &mut self.clients {
+            if let Err(error) = client.send(message) {
+                warn!("Dropping player {}: {}", player, error);
+                gone.push(player);
+            }
+        }
+        self.clients.retain(|&(player, _)| !gone.contains(&player));
+        Ok(())
+    }
+}
+
+// A player's copy of the game, stepped only by the ticks the server sends.
+pub struct Client {
+    pub player: usize,
+    pub simulation: Simulation,
+    server: Connection,
+}
+impl Client {
+    pub fn connect<A: ToSocketAddrs>(address: A) -> io::Result<Self> {
+        Client::join(TcpStream::connect(address)?)
+    }
+
+    // Join the game over a connection already made to the server, once the server welcomes it.
+    pub fn join(stream: TcpStream) -> io::Result<Self> {
+        let mut server = Connection::new(stream)?;
+        match server.recv()? {
+            Message::Welcome { player, players, seed, grid } => Ok(Client {
+                player: player,
+                simulation: Simulation::seeded(grid.0, grid.1, players, seed),
+                server: server,
+            }),
+            _ => Err(io::Error::new(ErrorKind::InvalidData, "expected a welcome from the server")),
+        }
+    }
+
+    // Ask the server to steer this player's snake on the next tick.
+    pub fn steer(&mut self, direction: (i8, i8)) -> io::Result<()> {
+        let tick = self.simulation.ticks();
+        self.server.send(&Message::Input {
+            tick: tick,
+            direction: direction,
+        })
+    }
+
+    pub fn restart(&mut self) -> io::Result<()> {
+        self.server.send(&Message::Restart)
+    }
+
+    // Apply every tick the server has sent so far, returning how many were applied.
+    pub fn update(&mut self) -> io::Result<usize> {
+        let mut applied = 0;
+        while let Some(message) = self.server.poll()? {
+            if self.apply(message)? {
+                applied += 1;
+            }
+        }
+        Ok(applied)
+    }
+
+    // Wait for the server to send the next tick and apply it.
+    pub fn wait(&mut self) -> io::Result<()> {
+        loop {
+            let message = self.server.recv()?;
+            if self.apply(message)? {
+                return Ok(());
+            }
+        }
+    }
+
+    fn apply(&mut self, message: Message) -> io::Result<bool> {
+        match message {
+            Message::Tick { tick, checksum, inputs } => {
+                self.simulation.step_with(&inputs);
+
+                if self.simulation.ticks() != tick || self.simulation.checksum() != checksum {
+                    return Err(io::Error::new(ErrorKind::InvalidData, format!("out of sync with the server at tick {}", tick)));
+                }
+                Ok(true)
+            },
+            Message::Reset => {
+                self.simulation.reset();
+                Ok(false)
+            },
+            _ => Ok(false),
+        }
+    }
+}
@@ -0,0 +1,142 @@
+
+// Rule systems that apply what happened when the snakes moved, subscribed to the events the
+// controller publishes.
+
+use specs::{Entities, Fetch, FetchMut, Join, ReadStorage, System, WriteStorage};
+
+use body::Followers;
+use components::{Extension, Parent, Poison, Shrink, Snake, Tick, Tile};
+use event::{Events, GameEvent, Reader, LEVEL_SIZE};
+use grid::{Grid, Layer};
+use mode::GameMode;
+use shrink::POISON;
+
+// How many segments a snake grows by for each food.
+pub const GROWTH: u16 = 5;
+
+// Takes eaten food off the board and grows the snake that ate it, or shrinks it for poison.
+#[derive(Default)]
+pub struct EatSystem {
+    reader: Reader,
+}
+impl<'a> System<'a> for EatSystem {
+    type SystemData = (
+        // Resources
+        Entities<'a>,
+        FetchMut<'a, Grid>,
+        FetchMut<'a, Events>,
+
+        // Components
+        WriteStorage<'a, Extension>,
+        WriteStorage<'a, Shrink>,
+        WriteStorage<'a, Snake>,
+        ReadStorage<'a, Poison>,
+    );
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, mut grid, mut events, mut extensions, mut shrinks, mut snakes, poisons) = data;
+
+        for event in events.read(&mut self.reader) {
+            let (by, food, tile) = match event {
+                GameEvent::FoodEaten { by, food, tile, .. } => (by, food, tile),
+                _ => continue,
+            };
+
+            // Only the first snake to reach a food gets it.
+            let (x, y) = (tile.x as usize, tile.y as usize);
+            if !grid.holds(Layer::Item, x, y, food) {
+                continue;
+            }
+
+            let poison = poisons.get(food).is_some();
+            grid.remove(x, y, food);
+            entities.delete(food);
+            events.publish(GameEvent::FoodSwallowed { by: by, food: food, poison: poison });
+            debug!("{:?} ate {:?}", by, food);
+
+            if poison {
+                let current = shrinks.get(by).map(|shrink| shrink.0).unwrap_or(0);
+                shrinks.insert(by, Shrink(current + POISON));
+                continue;
+            }
+
+            extensions.insert(by, Extension(GROWTH));
+
+            if let Some(snake) = snakes.get_mut(by) {
+                snake.score += 1;
+                if snake.score % LEVEL_SIZE == 0 {
+                    events.publish(GameEvent::LevelUp { snake: by, level: snake.score / LEVEL_SIZE });
+                }
+            }
+        }
+    }
+}
+
+// Ends the game when a snake dies, or in modes played to the last snake standing takes the dead
+// snakes off the board and ends the game once at most one is left.
+#[derive(Default)]
+pub struct DeathSystem {
+    reader: Reader,
+}
+impl<'a> System<'a> for DeathSystem {
+    type SystemData = (
+        // Resources
+        Entities<'a>,
+        Fetch<'a, GameMode>,
+        FetchMut<'a, Tick>,
+        FetchMut<'a, Grid>,
+        FetchMut<'a, Events>,
+
+        // Components
+        WriteStorage<'a, Extension>,
+        ReadStorage<'a, Snake>,
+        ReadStorage<'a, Parent>,
+        ReadStorage<'a, Tile>,
+    );
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, mode, mut tick, mut grid, mut events, mut extensions, snakes, parents, tiles) = data;
+
+        // A snake can die more than one way in a tick.
+        let mut dead = Vec::new();
+        for event in events.read(&mut self.reader) {
+            if let GameEvent::SnakeDied { who, cause, .. } = event {
+                if !dead.contains(&who) {
+                    info!("{:?} was killed by {:?}", who, cause);
+                    dead.push(who);
+                }
+            }
+        }
+
+        if dead.is_empty() || tick.game_over {
+            return;
+        }
+
+        let over = if mode.last_standing() {
+            let followers = Followers::new(&entities, &parents);
+            for &snake in &dead {
+                // Deleted entities are still around until the end of the tick, don't grow them.
+                extensions.remove(snake);
+
+                let end = snakes.get(snake).and_then(|snake| snake.end);
+                for segment in followers.body(snake, end) {
+                    if let Some(tile) = tiles.get(segment) {
+                        grid.remove(tile.x as usize, tile.y as usize, segment);
+                    }
+                    entities.delete(segment);
+                }
+            }
+
+            // Snakes that died on earlier ticks are already gone.
+            let total = snakes.join().count();
+            let alive = total - dead.len();
+            alive == 0 || (alive == 1 && total > 1)
+        }
+        else {
+            true
+        };
+
+        if over {
+            tick.game_over = true;
+            events.publish(GameEvent::GameOver);
+        }
+    }
+}
@@ -0,0 +1,296 @@
+
+// Power-ups, items placed on the board that give the snake picking them up a power for a number of
+// ticks. Ghost and shield change what the snake can run into, so the controller deals with those,
+// the rest are applied here.
+
+use rand::Rng;
+use specs::{Entities, Entity, Fetch, FetchMut, Join, ReadStorage, System, WriteStorage};
+
+use body::Followers;
+use components::*;
+use event::{Events, GameEvent, Reader};
+use grid::{Grid, Layer};
+use random::Random;
+
+pub const POWERS: [Power; 5] = [Power::Ghost, Power::SlowTime, Power::Magnet, Power::Shield, Power::Reverse];
+
+// How many ticks between each power-up being placed, and how many can be on the board at once.
+pub const POWER_UP_INTERVAL: u64 = 120;
+pub const MAX_POWER_UPS: usize = 2;
+
+// How many tiles away from the head a magnet pulls food from, and how many tiles a tick it pulls.
+pub const MAGNET_RANGE: i32 = 4;
+pub const MAGNET_PULL: usize = 2;
+
+// Ticks before a power runs out that the snake starts blinking.
+pub const WARNING: u32 = 10;
+
+// The part of the theme a power is drawn with.
+pub fn colour(power: Power) -> &'static str {
+    match power {
+        Power::Ghost => "ghost",
+        Power::SlowTime => "slow_time",
+        Power::Magnet => "magnet",
+        Power::Shield => "shield",
+        Power::Reverse => "reverse",
+    }
+}
+
+// Gives powers to the snakes that pick them up and counts them down.
+#[derive(Default)]
+pub struct PowerSystem {
+    reader: Reader,
+}
+impl<'a> System<'a> for PowerSystem {
+    type SystemData = (
+        // Resources
+        Entities<'a>,
+        FetchMut<'a, Tick>,
+        FetchMut<'a, Grid>,
+        FetchMut<'a, Events>,
+
+        // Components
+        WriteStorage<'a, Powers>,
+        WriteStorage<'a, Tile>,
+        WriteStorage<'a, Direction>,
+        WriteStorage<'a, Snake>,
+        WriteStorage<'a, Extension>,
+        ReadStorage<'a, Parent>,
+        ReadStorage<'a, Type>,
+        ReadStorage<'a, Poison>,
+    );
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            mut tick,
+            mut grid,
+            mut events,
+            mut powers,
+            mut tiles,
+            mut directions,
+            mut snakes,
+            mut extensions,
+            parents,
+            types,
+            poisons,
+        ) = data;
+
+        for event in events.read(&mut self.reader) {
+            match event {
+                GameEvent::PowerUpCollected { by, item, power, tile } => {
+                    // Only the first snake to reach a power-up gets it.
+                    let (x, y) = (tile.x as usize, tile.y as usize);
+                    if !grid.holds(Layer::Item, x, y, item) {
+                        continue;
+                    }
+
+                    grid.remove(x, y, item);
+                    entities.delete(item);
+                    debug!("{:?} picked up {}", by, power.name());
+
+                    if powers.get(by).is_none() {
+                        powers.insert(by, Powers::default());
+                    }
+                    if let Some(powers) = powers.get_mut(by) {
+                        powers.grant(power);
+                    }
+
+                    if power == Power::Reverse {
+                        let mut chain = Chain {
+                            entities: &entities,
+                            grid: &mut grid,
+                            tiles: &mut tiles,
+                            directions: &mut directions,
+                            snakes: &mut snakes,
+                            extensions: &mut extensions,
+                            parents: &parents,
+                        };
+                        chain.reverse(by);
+                    }
+                },
+                GameEvent::ShieldBroken { snake, .. } => {
+                    if let Some(powers) = powers.get_mut(snake) {
+                        powers.remove(Power::Shield);
+                    }
+                },
+                _ => { },
+            }
+        }
+
+        if !tick.ticked {
+            return;
+        }
+
+        for (entity, powers) in (&*entities, &mut powers).join() {
+            for power in powers.tick() {
+                events.publish(GameEvent::PowerExpired { snake: entity, power: power });
+            }
+        }
+
+        tick.slowed = powers.join().any(|powers| powers.has(Power::SlowTime));
+
+        // Pull food towards the tile in front of the nearest magnet in range, where the snake will
+        // run into it.
+        let magnets = (&snakes, &powers, &tiles).join()
+            .filter(|&(_, powers, _)| powers.has(Power::Magnet))
+            .map(|(snake, _, tile)| (tile.clone(), Tile {
+                x: tile.x + snake.queued_direction.0 as i32,
+                y: tile.y + snake.queued_direction.1 as i32,
+            }))
+            .collect::<Vec<_>>();
+        if magnets.is_empty() {
+            return;
+        }
+
+        for (food, kind, tile) in (&*entities, &types, &mut tiles).join() {
+            // Magnets leave poison alone, and food eaten this tick that is already off the grid.
+            if *kind != Type::Food || poisons.get(food).is_some() {
+                continue;
+            }
+            if !grid.holds(Layer::Item, tile.x as usize, tile.y as usize, food) {
+                continue;
+            }
+
+            let distance = |from: &Tile, to: &Tile| (from.x - to.x).abs() + (from.y - to.y).abs();
+            let target = magnets.iter()
+                .filter(|&&(ref head, _)| distance(head, tile) <= MAGNET_RANGE)
+                .min_by_key(|&&(ref head, _)| distance(head, tile))
+                .map(|&(_, ref target)| target.clone());
+            let target = match target {
+                Some(target) => target,
+                None => continue,
+            };
+
+            for _ in 0..MAGNET_PULL {
+                // Go along the longer way first, and the other way if that's blocked.
+                let (dx, dy) = (target.x - tile.x, target.y - tile.y);
+                let mut steps = vec![(dx.signum(), 0), (0, dy.signum())];
+                if dy.abs() > dx.abs() {
+                    steps.reverse();
+                }
+
+                let step = steps.into_iter()
+                    .filter(|&step| step != (0, 0))
+                    .find(|&(x, y)| grid.is_free((tile.x + x) as usize, (tile.y + y) as usize));
+                if let Some((x, y)) = step {
+                    grid.remove(tile.x as usize, tile.y as usize, food);
+                    tile.x += x;
+                    tile.y += y;
+                    grid.insert(tile.x as usize, tile.y as usize, food, Type::Food);
+                }
+            }
+        }
+    }
+}
+
+// The storages needed to rearrange a snake's segments.
+struct Chain<'s, 'a: 's> {
+    entities: &'s Entities<'a>,
+    grid: &'s mut Grid,
+    tiles: &'s mut WriteStorage<'a, Tile>,
+    directions: &'s mut WriteStorage<'a, Direction>,
+    snakes: &'s mut WriteStorage<'a, Snake>,
+    extensions: &'s mut WriteStorage<'a, Extension>,
+    parents: &'s ReadStorage<'a, Parent>,
+}
+impl<'s, 'a> Chain<'s, 'a> {
+    // Swap a snake's head and tail, so it heads back the way it came from the end of its tail.
+    //
+    // Segments stay in their order along the snake and swap tiles instead, so the head keeps its
+    // components. Segments that were only just grown and haven't moved onto the board yet are
+    // turned back into growth for the new tail.
+    fn reverse(&mut self, head: Entity) {
+        let end = self.snakes.get(head).and_then(|snake| snake.end);
+        let body = Followers::new(self.entities, self.parents).body(head, end);
+
+        let mut chain = vec![head];
+        let mut unplaced = Vec::new();
+        for &follower in &body[1..] {
+            // Everything behind an unplaced segment was grown after it.
+            let placed = self.tiles.get(follower)
+                .map(|tile| self.grid.holds(Layer::Actor, tile.x as usize, tile.y as usize, follower))
+                .unwrap_or(false);
+            if placed && unplaced.is_empty() {
+                chain.push(follower);
+            }
+            else {
+                unplaced.push(follower);
+            }
+        }
+
+        for &segment in &unplaced {
+            self.entities.delete(segment);
+        }
+        if !unplaced.is_empty() {
+            let growth = self.extensions.get(head).map(|extension| extension.0).unwrap_or(0);
+            self.extensions.insert(head, Extension(growth + unplaced.len() as u16));
+        }
+
+        let tiles = chain.iter().map(|&segment| self.tiles.get(segment).unwrap().clone()).collect::<Vec<_>>();
+        let headings = chain.iter().map(|&segment| self.directions.get(segment).unwrap().direction).collect::<Vec<_>>();
+        for (&segment, tile) in chain.iter().zip(&tiles) {
+            self.grid.remove(tile.x as usize, tile.y as usize, segment);
+        }
+
+        // Each segment heads towards the one that used to follow it, which is the way that one
+        // came from. The new tail heads the way the old head came from.
+        let last = chain.len() - 1;
+        for (index, &segment) in chain.iter().enumerate() {
+            let tile = tiles[last - index].clone();
+            let heading = if index < last { headings[last - index - 1] } else { headings[0] };
+            let heading = (-heading.0, -heading.1);
+
+            self.grid.insert(tile.x as usize, tile.y as usize, segment, Type::Snake);
+            self.tiles.insert(segment, tile);
+            self.directions.insert(segment, Direction { direction: heading, previous: None });
+            if index == 0 {
+                if let Some(snake) = self.snakes.get_mut(head) {
+                    snake.queued_direction = heading;
+                }
+            }
+        }
+
+        if let Some(snake) = self.snakes.get_mut(head) {
+            snake.length -= unplaced.len() as u32;
+            snake.end = if last > 0 { Some(chain[last]) } else { None };
+        }
+    }
+}
+
+// Places a random power-up on an empty part of the grid every so often.
+#[derive(Default)]
+pub struct PowerUpSpawnSystem;
+impl<'a> System<'a> for PowerUpSpawnSystem {
+    type SystemData = (
+        // Resources
+        Entities<'a>,
+        Fetch<'a, Tick>,
+        FetchMut<'a, Grid>,
+        FetchMut<'a, Random>,
+        FetchMut<'a, Events>,
+
+        // Components
+        WriteStorage<'a, Tile>,
+        WriteStorage<'a, Type>,
+    );
+    fn run(&mut self, (entities, tick, mut grid, mut random, mut events, mut tiles, mut types): Self::SystemData) {
+        if !tick.ticked || tick.ticks % POWER_UP_INTERVAL != 0 {
+            return;
+        }
+
+        let placed = types.join().filter(|kind| match **kind { Type::PowerUp(_) => true, _ => false }).count();
+        if placed >= MAX_POWER_UPS {
+            return;
+        }
+
+        let power = *random.choose(&POWERS).unwrap();
+        if let Some(&(x, y)) = random.choose(&grid.free()) {
+            let item = entities.create();
+            let tile = Tile { x: x as i32, y: y as i32 };
+            tiles.insert(item, tile.clone());
+            types.insert(item, Type::PowerUp(power));
+            grid.insert(x, y, item, Type::PowerUp(power));
+            events.publish(GameEvent::PowerUpSpawned { item: item, power: power, tile: tile });
+        }
+    }
+}
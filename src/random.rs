@@ -0,0 +1,64 @@
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rand::Rng;
+
+// Random numbers for the game rules, seeded so every peer in a networked game makes the same choices.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Random {
+    // What the game being played was seeded with, kept for the record.
+    pub seed: u64,
+
+    state: [u32; 4],
+}
+impl Random {
+    pub fn new(seed: u64) -> Self {
+        // Spread the seed over the xorshift state, which must not be all zeroes.
+        let mut split = seed;
+        let mut next = || {
+            split = split.wrapping_add(0x9E37_79B9_7F4A_7C15);
+            let mut z = split;
+            z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+            z ^ (z >> 31)
+        };
+
+        let (a, b) = (next(), next());
+        let mut state = [a as u32, (a >> 32) as u32, b as u32, (b >> 32) as u32];
+        if state == [0; 4] {
+            state[0] = 1;
+        }
+
+        Random {
+            seed: seed,
+            state: state,
+        }
+    }
+
+    // Start over from a seed drawn from the numbers so far, unless none have been used since the
+    // last one. Every game then has a seed of its own to record, and peers restarting together
+    // draw the same one.
+    pub fn reseed(&mut self) {
+        if *self != Random::new(self.seed) {
+            let seed = ((self.next_u32() as u64) << 32) | self.next_u32() as u64;
+            *self = Random::new(seed);
+        }
+    }
+}
+impl Rng for Random {
+    fn next_u32(&mut self) -> u32 {
+        // xorshift128
+        let t = self.state[0] ^ (self.state[0] << 11);
+        self.state[0] = self.state[1];
+        self.state[1] = self.state[2];
+        self.state[2] = self.state[3];
+        self.state[3] = self.state[3] ^ (self.state[3] >> 19) ^ t ^ (t >> 8);
+        self.state[3]
+    }
+}
+
+// A seed that differs between runs.
+pub fn seed() -> u64 {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    now.as_secs() ^ ((now.subsec_nanos() as u64) << 32)
+}
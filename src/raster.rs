@@ -0,0 +1,258 @@
+
+// Draws what the window would show as text instead of pixels, so how the game looks can be checked
+// without a window or a graphics card.
+//
+// Each character is whatever the camera sees at the middle of its part of the screen, found by
+// putting that point through the camera and every mesh where its `LocalTransform` puts it, and
+// stands for the theme colour it's drawn in. Snake pieces are looked up on the sprite sheet, so
+// they're only drawn where the sheet has the piece on it.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use amethyst::ecs::components::{LocalTransform, Transform};
+use amethyst::ecs::resources::{Camera, Projection, ScreenDimensions};
+use amethyst::renderer::VertexPosNormal;
+use specs::{Join, RunNow, World};
+
+use components::{Appearance, Board, Flash, GridLine, Particle, Sprite};
+use sprite::{self, SpriteSystem};
+use state;
+use theme::{Colour, Theme};
+use tile::{Layout, TileSystem};
+
+// A picture of the screen a row of text at a time, with the colour each character stands for.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Picture {
+    pub rows: Vec<String>,
+    pub legend: Vec<(char, String, Colour)>,
+}
+impl fmt::Display for Picture {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for row in &self.rows {
+            writeln!(f, "{}", row)?;
+        }
+        writeln!(f)?;
+        for &(symbol, ref name, colour) in &self.legend {
+            writeln!(f, "'{}' {} {:?}", symbol, name, colour)?;
+        }
+        Ok(())
+    }
+}
+
+// Set a world up to be drawn the way the window does, on a screen of a size in pixels.
+pub fn offscreen(world: &mut World, width: f32, height: f32) {
+    world.register::<LocalTransform>();
+    world.register::<Transform>();
+    world.register::<Board>();
+    world.register::<GridLine>();
+    world.register::<Particle>();
+    world.register::<Flash>();
+    world.register::<Sprite>();
+    world.register::<Appearance>();
+
+    world.add_resource(ScreenDimensions { w: width, h: height, aspect_ratio: width / height });
+    world.add_resource(Camera {
+        proj: Projection::Orthographic {
+            left: 0.0,
+            right: 1.0,
+            bottom: 0.0,
+            top: 1.0,
+            near: -1.0,
+            far: 1.0,
+        },
+        eye: [0.0; 3],
+        target: [0.0; 3],
+        up: [0.0; 3],
+    });
+    world.add_resource(Layout::default());
+    state::camera(world);
+}
+
+// Do what the window does each frame before drawing: put up the scenery once, dress everything in
+// the theme's colours and move it all into place.
+pub fn update(world: &mut World, theme: &Theme) {
+    if world.read::<Board>().join().next().is_none() {
+        state::build_scenery(world, theme);
+    }
+
+    // New snake segments need to know which piece they are before they're dressed as one, and a
+    // transform from being dressed before they can be turned to match it.
+    SpriteSystem::default().run_now(&world.res);
+    for (entity, appearance) in state::appearances(world, theme) {
+        state::wear(world, entity, appearance);
+    }
+    SpriteSystem::default().run_now(&world.res);
+    TileSystem::default().run_now(&world.res);
+}
+
+// Draw the world `columns` characters across and `rows` down.
+pub fn draw(world: &World, theme: &Theme, columns: usize, rows: usize) -> Picture {
+    let dimensions = world.read_resource::<ScreenDimensions>();
+    let camera = world.read_resource::<Camera>();
+    let locals = world.read::<LocalTransform>();
+    let appearances = world.read::<Appearance>();
+    let meshes = state::meshes().into_iter().collect::<HashMap<_, _>>();
+    let mask = sprite::mask();
+
+    let colours = theme.textures().into_iter().collect::<HashMap<_, _>>();
+    let background = theme.texture("background");
+
+    let mut used = Vec::new();
+    let mut lines = Vec::with_capacity(rows);
+    for row in 0..rows {
+        let mut line = String::with_capacity(columns);
+        for column in 0..columns {
+            let screen = (
+                (column as f32 + 0.5) * dimensions.w / columns as f32,
+                (row as f32 + 0.5) * dimensions.h / rows as f32,
+            );
+
+            // The nearest thing to the camera is drawn over the rest.
+            let mut nearest: Option<(f32, &Appearance)> = None;
+            if let Some(point) = unproject(&camera, &dimensions, screen) {
+                for (local, appearance) in (&locals, &appearances).join() {
+                    let depth = local.translation[2];
+                    if nearest.map(|(nearest, _)| depth <= nearest).unwrap_or(false) {
+                        continue;
+                    }
+                    // Sprite sheets are see-through around the pieces drawn on them.
+                    let covers = meshes.get(appearance.mesh)
+                        .and_then(|vertices| covers(vertices, local, point))
+                        .map(|coordinates| match sprite::drawn_in(&appearance.colour) {
+                            Some(_) => lit(&mask, coordinates),
+                            None => true,
+                        })
+                        .unwrap_or(false);
+                    if covers {
+                        nearest = Some((depth, appearance));
+                    }
+                }
+            }
+
+            let texture = nearest
+                .map(|(_, appearance)| sprite::drawn_in(&appearance.colour).unwrap_or(&appearance.colour).to_owned())
+                .unwrap_or_else(|| background.clone());
+            if !used.contains(&texture) {
+                used.push(texture.clone());
+            }
+            line.push(symbol(&texture));
+        }
+        lines.push(line);
+    }
+
+    let mut legend = used.into_iter()
+        .map(|texture| {
+            let colour = colours.get(&texture).cloned().unwrap_or(theme.background);
+            (symbol(&texture), texture, colour)
+        })
+        .collect::<Vec<_>>();
+    legend.sort_by(|a, b| a.1.cmp(&b.1));
+
+    Picture {
+        rows: lines,
+        legend: legend,
+    }
+}
+
+// Where on the board a point on the screen is, going back through the camera. Only orthographic
+// cameras are drawn, the game doesn't use anything else.
+fn unproject(camera: &Camera, dimensions: &ScreenDimensions, screen: (f32, f32)) -> Option<(f32, f32)> {
+    match camera.proj {
+        Projection::Orthographic { left, right, bottom, top, .. } => {
+            // From the top left of the screen to between -1 and 1 with y going up, the way the
+            // projection leaves it.
+            let x = screen.0 / dimensions.w * 2.0 - 1.0;
+            let y = 1.0 - screen.1 / dimensions.h * 2.0;
+
+            // The camera looks straight down at the board, only where it sits across it matters.
+            Some((
+                left + (x + 1.0) / 2.0 * (right - left) + camera.eye[0],
+                bottom + (y + 1.0) / 2.0 * (top - bottom) + camera.eye[1],
+            ))
+        },
+        _ => None,
+    }
+}
+
+// Where on the texture a mesh placed by a transform is at a point, if it covers it, undoing the
+// translation, the rotation around z and the scale in turn.
+fn covers(vertices: &[VertexPosNormal], local: &LocalTransform, point: (f32, f32)) -> Option<[f32; 2]> {
+    let (x, y) = (point.0 - local.translation[0], point.1 - local.translation[1]);
+    let angle = 2.0 * local.rotation[3].atan2(local.rotation[0]);
+    let (sin, cos) = (-angle).sin_cos();
+    let (x, y) = (x * cos - y * sin, x * sin + y * cos);
+    if local.scale[0] == 0.0 || local.scale[1] == 0.0 {
+        return None;
+    }
+    let (x, y) = (x / local.scale[0], y / local.scale[1]);
+
+    vertices.chunks(3)
+        .filter(|triangle| triangle.len() == 3)
+        .filter_map(|triangle| inside(triangle, (x, y)))
+        .next()
+}
+
+// Where on the texture a point inside a triangle is, whichever way round it winds, blending the
+// corners' texture coordinates by how close the point is to each.
+fn inside(triangle: &[VertexPosNormal], (x, y): (f32, f32)) -> Option<[f32; 2]> {
+    let side = |a: &VertexPosNormal, b: &VertexPosNormal| {
+        (b.pos[0] - a.pos[0]) * (y - a.pos[1]) - (b.pos[1] - a.pos[1]) * (x - a.pos[0])
+    };
+    let sides = [
+        side(&triangle[1], &triangle[2]),
+        side(&triangle[2], &triangle[0]),
+        side(&triangle[0], &triangle[1]),
+    ];
+    let within = sides.iter().all(|&side| side >= 0.0) || sides.iter().all(|&side| side <= 0.0);
+    let area = sides[0] + sides[1] + sides[2];
+    if !within || area == 0.0 {
+        return None;
+    }
+
+    let mut coordinates = [0.0; 2];
+    for (corner, side) in triangle.iter().zip(&sides) {
+        coordinates[0] += corner.tex_coord[0] * side / area;
+        coordinates[1] += corner.tex_coord[1] * side / area;
+    }
+    Some(coordinates)
+}
+
+// Whether the pixel of the sprite sheet at some texture coordinates is part of a piece.
+fn lit(mask: &[bool], coordinates: [f32; 2]) -> bool {
+    let pixel = |coordinate: f32, len: usize| ((coordinate * len as f32) as usize).min(len - 1);
+    let column = pixel(coordinates[0].max(0.0), sprite::SHEET.0);
+    let row = pixel(coordinates[1].max(0.0), sprite::SHEET.1);
+    mask[row * sprite::SHEET.0 + column]
+}
+
+// The character a texture is drawn as, from the part of the theme it colours. Snakes are lettered
+// by player, capitals for their heads.
+fn symbol(texture: &str) -> char {
+    let part = texture.rsplit('/').next().unwrap_or(texture);
+    let player = |prefix: &str, first: u8| part[prefix.len()..].parse::<u8>().ok()
+        .map(|player| (first + player % 26) as char)
+        .unwrap_or('?');
+
+    match part {
+        "background" => ':',
+        "board" => '.',
+        "grid_lines" => '+',
+        "food" => '*',
+        "walls" => '#',
+        "hud" => '!',
+        "poison" => '%',
+        "portal" => 'O',
+        "gate" => '>',
+        "ice" => '~',
+        "conveyor" => '=',
+        "ghost" => '$',
+        "slow_time" => '&',
+        "magnet" => 'M',
+        "shield" => ')',
+        "reverse" => 'R',
+        _ if part.starts_with("head") => player("head", b'A'),
+        _ if part.starts_with("body") => player("body", b'a'),
+        _ => '?',
+    }
+}
@@ -0,0 +1,121 @@
+
+// Snakes losing segments off the end of their tail, the opposite of `ExtendSystem`. Snakes shrink
+// from eating poison, from breaking a shield and, when the game mode wants it, slowly over time.
+
+use specs::{Entities, Entity, Fetch, FetchMut, Join, ReadStorage, System, WriteStorage};
+
+use components::{Extension, Parent, Shrink, Snake, Tick, Tile};
+use event::{Cause, Events, GameEvent, Reader};
+use grid::Grid;
+
+// How many segments a snake loses for eating poison or breaking its shield.
+pub const POISON: u16 = 3;
+pub const HIT_PENALTY: u16 = 2;
+
+// How many ticks between every snake losing a segment, never if zero.
+#[derive(Clone, Debug, Default)]
+pub struct Decay {
+    pub every: u64,
+}
+
+// Add to how many segments a snake will lose this tick.
+fn add(shrinks: &mut WriteStorage<Shrink>, snake: Entity, amount: u16) {
+    let current = shrinks.get(snake).map(|shrink| shrink.0).unwrap_or(0);
+    shrinks.insert(snake, Shrink(current + amount));
+}
+
+#[derive(Default)]
+pub struct ShrinkSystem {
+    reader: Reader,
+}
+impl<'a> System<'a> for ShrinkSystem {
+    type SystemData = (
+        // Resources
+        Entities<'a>,
+        Fetch<'a, Tick>,
+        Fetch<'a, Decay>,
+        FetchMut<'a, Grid>,
+        FetchMut<'a, Events>,
+
+        // Components
+        WriteStorage<'a, Shrink>,
+        WriteStorage<'a, Extension>,
+        WriteStorage<'a, Snake>,
+        ReadStorage<'a, Parent>,
+        ReadStorage<'a, Tile>,
+    );
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            tick,
+            decay,
+            mut grid,
+            mut events,
+            mut shrinks,
+            mut extensions,
+            mut snakes,
+            parents,
+            tiles,
+        ) = data;
+
+        for event in events.read(&mut self.reader) {
+            if let GameEvent::ShieldBroken { snake, .. } = event {
+                add(&mut shrinks, snake, HIT_PENALTY);
+            }
+        }
+
+        if !tick.ticked {
+            return;
+        }
+
+        if decay.every > 0 && tick.ticks % decay.every == 0 {
+            for (entity, _) in (&*entities, &snakes).join() {
+                add(&mut shrinks, entity, 1);
+            }
+        }
+
+        let mut done = Vec::new();
+        for (entity, snake, shrink) in (&*entities, &mut snakes, &mut shrinks).join() {
+            done.push(entity);
+
+            // Growth that hasn't happened yet goes first.
+            let mut remaining = shrink.0;
+            if let Some(extension) = extensions.get_mut(entity) {
+                let cancelled = remaining.min(extension.0);
+                extension.0 -= cancelled;
+                remaining -= cancelled;
+            }
+            if extensions.get(entity).map(|extension| extension.0 == 0).unwrap_or(false) {
+                extensions.remove(entity);
+            }
+
+            for _ in 0..remaining {
+                let end = match snake.end {
+                    Some(end) => end,
+                    None => {
+                        let tile = tiles.get(entity).cloned().unwrap_or_default();
+                        events.publish(GameEvent::SnakeDied { who: entity, cause: Cause::Shrunk, tile: tile });
+                        break;
+                    },
+                };
+
+                // Tails that haven't moved onto the board yet aren't in the grid, which `remove`
+                // leaves alone.
+                if let Some(tile) = tiles.get(end) {
+                    grid.remove(tile.x as usize, tile.y as usize, end);
+                }
+                entities.delete(end);
+
+                snake.end = match parents.get(end) {
+                    Some(parent) if parent.0 != entity => Some(parent.0),
+                    _ => None,
+                };
+                snake.length -= 1;
+            }
+        }
+
+        for entity in done {
+            shrinks.remove(entity);
+        }
+    }
+}
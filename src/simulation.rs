@@ -0,0 +1,375 @@
+
+use std::io;
+use std::time::Duration;
+
+use specs::{Dispatcher, DispatcherBuilder, Entity, Join, RunNow, World};
+
+use arena::{Arena, ArenaSystem};
+use components::*;
+use controller::ControllerSystem;
+use event::{Events, GameEvent};
+use extend::ExtendSystem;
+use food::{ExpirySystem, FoodSpawner, LiveFoodSystem, SpawnRules};
+use grid::{Grid, Layer};
+use level::Level;
+use mode::{GameMode, ModeSystem};
+use outcome::{DeathSystem, EatSystem};
+use power::{PowerSystem, PowerUpSpawnSystem};
+use random::{self, Random};
+use shrink::{Decay, ShrinkSystem};
+use snapshot::Snapshot;
+use stream::{Stream, StreamSystem};
+use telemetry::TelemetrySystem;
+use tick::{self, Clock, TickSystem};
+
+// Add the resources and components the game rules need to a world.
+pub fn register(world: &mut World, x: usize, y: usize, seed: u64) {
+    world.add_resource::<Tick>(Tick::default());
+    world.add_resource::<Grid>(Grid::new(x, y));
+    world.add_resource::<Random>(Random::new(seed));
+    world.add_resource::<Events>(Events::default());
+    world.add_resource::<Clock>(Clock::default());
+    world.add_resource::<Level>(Level::default());
+    world.add_resource::<SpawnRules>(SpawnRules::default());
+    world.add_resource::<Decay>(Decay::default());
+    world.add_resource::<GameMode>(GameMode::default());
+    world.add_resource::<Arena>(Arena::default());
+
+    world.register::<Snake>();
+    world.register::<Player>();
+    world.register::<Type>();
+    world.register::<Parent>();
+    world.register::<Direction>();
+    world.register::<Extension>();
+    world.register::<Tile>();
+    world.register::<Previous>();
+    world.register::<Powers>();
+    world.register::<Expiry>();
+    world.register::<Live>();
+    world.register::<Shrink>();
+    world.register::<Poison>();
+}
+
+// Add the systems that make up the game rules, run after the systems in `after`.
+pub fn rules<'a, 'b>(builder: DispatcherBuilder<'a, 'b>, after: &[&str]) -> DispatcherBuilder<'a, 'b> {
+    movement(builder, after)
+        .add(PowerSystem::default(), "powers", &["extend"])
+        .add(LiveFoodSystem::default(), "live_food", &["powers"])
+        .add(ExpirySystem::default(), "expiry", &["live_food"])
+        .add(PowerUpSpawnSystem::default(), "power_ups", &["expiry"])
+        .add(FoodSpawner::default(), "food", &["power_ups"])
+}
+
+// Add the rules that move, feed, shrink, kill and grow the snakes, without anything that puts food
+// or power-ups on the board or changes them, so tests and benchmarks control what's there. The last
+// of them is called "extend".
+pub fn movement<'a, 'b>(builder: DispatcherBuilder<'a, 'b>, after: &[&str]) -> DispatcherBuilder<'a, 'b> {
+    builder
+        .add(ControllerSystem::default(), "controller", after)
+        .add(EatSystem::default(), "eat", &["controller"])
+        .add(ShrinkSystem::default(), "shrink", &["eat"])
+        .add(ArenaSystem::default(), "arena", &["shrink"])
+        .add(DeathSystem::default(), "death", &["arena"])
+        .add(ModeSystem::default(), "mode", &["death"])
+        .add(ExtendSystem::default(), "extend", &["mode"])
+}
+
+// Clear out the previous game and set up the snakes for a new one, returning their heads. The new
+// game gets a seed of its own unless the last one never drew a number.
+pub fn reset(world: &mut World, players: usize) -> Vec<Entity> {
+    let len = world.read_resource::<Grid>().len;
+    clear(world, len);
+    world.write_resource::<Random>().reseed();
+
+    let mode = *world.read_resource::<GameMode>();
+    mode.apply(world);
+
+    let level = (*world.read_resource::<Level>()).clone();
+    level.build(world);
+
+    (0..players).map(|player| spawn_snake(world, player, spawn_tile(player, players, len))).collect()
+}
+
+// Remove every entity and start the tick count over on an empty grid.
+pub fn clear(world: &mut World, len: (usize, usize)) {
+    // Remove all previous entities.
+    for entity in world.entities().join()
+    {
+        world.entities().delete(entity);
+    }
+    // World::maintain will clean up the entities immediately.
+    // (Otherwise weird stuff happens?)
+    world.maintain();
+
+    // Reset tick and grid state.
+    let mut tick = world.write_resource::<Tick>();
+    *tick = Tick::default();
+
+    let mut grid = world.write_resource::<Grid>();
+    *grid = Grid::new(len.0, len.1);
+}
+
+// Where a player's snake starts, spread out along the bottom of the board.
+fn spawn_tile(player: usize, players: usize, len: (usize, usize)) -> Tile {
+    let x = match (players, player) {
+        (1, _) | (2, 0) => len.0 / 2 - len.0 / 4,
+        (2, _) => len.0 / 2 + len.0 / 4,
+        _ => (2 * player + 1) * len.0 / (2 * players),
+    };
+
+    Tile { x: x as i32, y: (3 * len.1 / 4) as i32 }
+}
+
+// Create the head of a snake heading up the board.
+pub fn spawn_snake(world: &mut World, player: usize, tile: Tile) -> Entity {
+    world.create_entity()
+        .with(tile)
+        .with(Snake {
+            queued_direction: (0, -1),
+            length: 1,
+            score: 0,
+            end: None,
+        })
+        .with(Player(player))
+        .with(Type::Snake)
+        .with(Direction::default())
+        .with(Extension(6)) // Start the snake off with 7 pieces (head + 6 tail pieces)
+        .build()
+}
+
+// The game rules running without a window, for frontends that draw the grid themselves.
+pub struct Simulation {
+    pub world: World,
+    pub players: usize,
+    dispatcher: Dispatcher<'static, 'static>,
+    ticks: TickSystem,
+    stream: StreamSystem,
+    telemetry: TelemetrySystem,
+}
+impl Simulation {
+    pub fn new(x: usize, y: usize, players: usize) -> Self {
+        Simulation::seeded(x, y, players, random::seed())
+    }
+
+    // A simulation that makes the same choices as any other with the same seed and inputs.
+    pub fn seeded(x: usize, y: usize, players: usize, seed: u64) -> Self {
+        let mut world = World::new();
+        register(&mut world, x, y, seed);
+
+        let mut simulation = Simulation {
+            world: world,
+            players: players,
+            dispatcher: rules(DispatcherBuilder::new(), &[]).build(),
+            ticks: TickSystem::default(),
+            stream: StreamSystem(None),
+            telemetry: TelemetrySystem::default(),
+        };
+        simulation.reset();
+        simulation
+    }
+
+    pub fn reset(&mut self) {
+        reset(&mut self.world, self.players);
+    }
+
+    // Play on a level from now on, starting a new game on it.
+    pub fn level(&mut self, level: Level) {
+        *self.world.write_resource::<Level>() = level;
+        self.reset();
+    }
+
+    // Play a mode from now on, starting a new game in it.
+    pub fn mode(&mut self, mode: GameMode) {
+        *self.world.write_resource::<GameMode>() = mode;
+        self.reset();
+    }
+
+    // Send a frame to spectators after every tick.
+    pub fn stream(&mut self, stream: Stream) {
+        self.stream = StreamSystem(Some(stream));
+    }
+
+    // Record every game played as a line of telemetry.
+    pub fn record(&mut self, telemetry: TelemetrySystem) {
+        self.telemetry = telemetry;
+    }
+
+    // Save the whole game under a key so it can be picked up again later.
+    pub fn save(&self, key: &str) -> io::Result<()> {
+        self.snapshot().save(key)
+    }
+
+    // Continue a game saved under a key.
+    pub fn load(&mut self, key: &str) -> io::Result<()> {
+        let snapshot = Snapshot::load(key)?;
+        self.restore(&snapshot);
+        Ok(())
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot::capture(&self.world, self.players)
+    }
+
+    // Carry on from a snapshot, with as many players as it was taken with.
+    pub fn restore(&mut self, snapshot: &Snapshot) {
+        snapshot.restore(&mut self.world);
+        self.players = snapshot.players;
+    }
+
+    // Queue a direction for a player's snake.
+    pub fn steer(&mut self, player: usize, wanted: (i8, i8)) {
+        let entities = self.world.entities();
+        let directions = self.world.read::<Direction>();
+        let players = self.world.read::<Player>();
+        let mut snakes = self.world.write::<Snake>();
+        let mut events = self.world.write_resource::<Events>();
+
+        for (entity, snake, direction, owner) in (&*entities, &mut snakes, &directions, &players).join() {
+            let queued = snake.queued_direction;
+            if owner.0 == player && snake.steer(direction, wanted) && snake.queued_direction != queued {
+                events.publish(GameEvent::SnakeSteered { snake: entity, direction: wanted });
+            }
+        }
+    }
+
+    // Advance the clock by how long the last frame took, running the rules if a tick elapsed.
+    pub fn update(&mut self, delta: Duration) -> bool {
+        let elapsed = {
+            let mut tick = self.world.write_resource::<Tick>();
+            let mut events = self.world.write_resource::<Events>();
+            self.ticks.advance(delta, &mut tick, &mut events)
+        };
+
+        if elapsed {
+            self.dispatch();
+        }
+        elapsed
+    }
+
+    // Apply the inputs players made during a tick, in the order they were made, then run the tick.
+    pub fn step_with(&mut self, inputs: &[(usize, (i8, i8))]) {
+        for &(player, wanted) in inputs {
+            self.steer(player, wanted);
+        }
+
+        self.step();
+    }
+
+    // Run the rules for exactly one tick.
+    pub fn step(&mut self) {
+        tick::next(&mut self.world.write_resource::<Tick>(), &mut self.world.write_resource::<Events>());
+        self.dispatch();
+    }
+
+    pub fn ticks(&self) -> u64 {
+        self.world.read_resource::<Tick>().ticks
+    }
+
+    pub fn game_over(&self) -> bool {
+        self.world.read_resource::<Tick>().game_over
+    }
+
+    // Hash of what is on the board, for checking that two simulations have not drifted apart. Peers
+    // built for other platforms or with other compilers have to agree on it, so it's FNV-1a over
+    // integers of a fixed size rather than whatever the standard library hashes with.
+    pub fn checksum(&self) -> u64 {
+        let tick = self.world.read_resource::<Tick>();
+        let grid = self.world.read_resource::<Grid>();
+        let players = self.world.read::<Player>();
+
+        let mut hash = Fnv::new();
+        hash.u64(tick.ticks);
+        hash.u8(tick.game_over as u8);
+        hash.u32(grid.len.0 as u32);
+        hash.u32(grid.len.1 as u32);
+        for y in 0..grid.len.1 {
+            for x in 0..grid.len.0 {
+                for layer in &[Layer::Terrain, Layer::Item, Layer::Actor] {
+                    if let Some(occupant) = grid.get(*layer, x, y) {
+                        hash.u32(x as u32);
+                        hash.u32(y as u32);
+                        hash.kind(occupant.kind);
+                        match players.get(occupant.entity) {
+                            Some(player) => {
+                                hash.u8(1);
+                                hash.u32(player.0 as u32);
+                            },
+                            None => hash.u8(0),
+                        }
+                    }
+                }
+            }
+        }
+
+        hash.0
+    }
+
+    fn dispatch(&mut self) {
+        self.dispatcher.dispatch(&mut self.world.res);
+        self.world.maintain();
+        self.stream.run_now(&self.world.res);
+        self.telemetry.run_now(&self.world.res);
+    }
+}
+
+// 64 bit FNV-1a, fed integers a byte at a time from the lowest byte up.
+struct Fnv(u64);
+impl Fnv {
+    fn new() -> Self {
+        Fnv(0xcbf29ce484222325)
+    }
+
+    fn u8(&mut self, byte: u8) {
+        self.0 ^= byte as u64;
+        self.0 = self.0.wrapping_mul(0x100000001b3);
+    }
+
+    fn u32(&mut self, value: u32) {
+        for shift in 0..4 {
+            self.u8((value >> (shift * 8)) as u8);
+        }
+    }
+
+    fn u64(&mut self, value: u64) {
+        for shift in 0..8 {
+            self.u8((value >> (shift * 8)) as u8);
+        }
+    }
+
+    fn way(&mut self, way: (i8, i8)) {
+        self.u8(way.0 as u8);
+        self.u8(way.1 as u8);
+    }
+
+    fn kind(&mut self, kind: Type) {
+        match kind {
+            Type::Snake => self.u8(0),
+            Type::Wall => self.u8(1),
+            Type::Food => self.u8(2),
+            Type::PowerUp(power) => {
+                self.u8(3);
+                self.u8(match power {
+                    Power::Ghost => 0,
+                    Power::SlowTime => 1,
+                    Power::Magnet => 2,
+                    Power::Shield => 3,
+                    Power::Reverse => 4,
+                });
+            },
+            Type::Portal { x, y } => {
+                self.u8(4);
+                self.u32(x as u32);
+                self.u32(y as u32);
+            },
+            Type::Gate(way) => {
+                self.u8(5);
+                self.way(way);
+            },
+            Type::Ice => self.u8(6),
+            Type::Conveyor(way) => {
+                self.u8(7);
+                self.way(way);
+            },
+        }
+    }
+}
@@ -0,0 +1,240 @@
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, ErrorKind};
+use std::path::PathBuf;
+
+use serde_json;
+use specs::{Entity, Join, World};
+
+use arena::Arena;
+use components::*;
+use grid::Grid;
+use level::Level;
+use mode::GameMode;
+use random::Random;
+use simulation;
+
+// Directory saved games are kept in.
+pub const SAVE_DIR: &'static str = "saves";
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SavedSnake {
+    pub queued_direction: (i8, i8),
+    pub length: u32,
+    pub score: u32,
+
+    // Index of the end of the tail in the saved entities.
+    pub end: Option<usize>,
+}
+
+// Every component of an entity on the board. Links to other entities are indices into the
+// snapshot's entities, since entities themselves are different once restored.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SavedEntity {
+    pub tile: Tile,
+
+    // Whether the entity was in the grid, tails that haven't moved yet aren't.
+    pub placed: bool,
+
+    pub kind: Option<Type>,
+    pub player: Option<Player>,
+    pub direction: Option<Direction>,
+    pub extension: Option<u16>,
+    pub parent: Option<usize>,
+    pub snake: Option<SavedSnake>,
+    pub powers: Option<Powers>,
+    pub expiry: Option<Expiry>,
+    pub live: Option<Live>,
+    pub poison: bool,
+}
+
+// The whole state of a game, enough to carry on exactly where it was left.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub grid: (usize, usize),
+    pub players: usize,
+    pub ticks: u64,
+    pub game_over: bool,
+    pub random: Random,
+    pub entities: Vec<SavedEntity>,
+    pub mode: GameMode,
+    pub arena: Arena,
+
+    // Whether time was slowed by a power-up, and the level the game restarts on.
+    pub slowed: bool,
+    pub level: Level,
+}
+impl Snapshot {
+    // The game in a world, played by a number of players. Snakes that have died and been taken off
+    // the board are still counted, so the game restarts with everyone in it.
+    pub fn capture(world: &World, players: usize) -> Snapshot {
+        let tick = world.read_resource::<Tick>();
+        let grid = world.read_resource::<Grid>();
+        let entities = world.entities();
+        let tiles = world.read::<Tile>();
+        let types = world.read::<Type>();
+        let owners = world.read::<Player>();
+        let directions = world.read::<Direction>();
+        let extensions = world.read::<Extension>();
+        let parents = world.read::<Parent>();
+        let snakes = world.read::<Snake>();
+        let powers = world.read::<Powers>();
+        let expiries = world.read::<Expiry>();
+        let lives = world.read::<Live>();
+        let poisons = world.read::<Poison>();
+
+        let saved = (&*entities, &tiles).join().map(|(entity, _)| entity).collect::<Vec<_>>();
+        let index = saved.iter()
+            .enumerate()
+            .map(|(index, &entity)| (entity, index))
+            .collect::<HashMap<_, _>>();
+
+        let saved = saved.iter().map(|&entity| {
+            let tile = tiles.get(entity).unwrap().clone();
+            let kind = types.get(entity).cloned();
+            let placed = kind
+                .map(|kind| grid.holds(kind.layer(), tile.x as usize, tile.y as usize, entity))
+                .unwrap_or(false);
+
+            SavedEntity {
+                tile: tile,
+                placed: placed,
+                kind: kind,
+                player: owners.get(entity).cloned(),
+                direction: directions.get(entity).cloned(),
+                extension: extensions.get(entity).map(|extension| extension.0),
+                parent: parents.get(entity).and_then(|parent| index.get(&parent.0).cloned()),
+                snake: snakes.get(entity).map(|snake| SavedSnake {
+                    queued_direction: snake.queued_direction,
+                    length: snake.length,
+                    score: snake.score,
+                    end: snake.end.and_then(|end| index.get(&end).cloned()),
+                }),
+                powers: powers.get(entity).cloned(),
+                expiry: expiries.get(entity).cloned(),
+                live: lives.get(entity).cloned(),
+                poison: poisons.get(entity).is_some(),
+            }
+        }).collect::<Vec<_>>();
+
+        Snapshot {
+            grid: grid.len,
+            players: players,
+            ticks: tick.ticks,
+            game_over: tick.game_over,
+            random: (*world.read_resource::<Random>()).clone(),
+            entities: saved,
+            mode: *world.read_resource::<GameMode>(),
+            arena: (*world.read_resource::<Arena>()).clone(),
+            slowed: tick.slowed,
+            level: (*world.read_resource::<Level>()).clone(),
+        }
+    }
+
+    // Replace whatever is in the world with this snapshot, returning the heads of the snakes in
+    // player order.
+    pub fn restore(&self, world: &mut World) -> Vec<Entity> {
+        simulation::clear(world, self.grid);
+        *world.write_resource::<GameMode>() = self.mode;
+        self.mode.apply(world);
+        *world.write_resource::<Arena>() = self.arena.clone();
+        *world.write_resource::<Level>() = self.level.clone();
+        {
+            let mut tick = world.write_resource::<Tick>();
+            tick.ticks = self.ticks;
+            tick.game_over = self.game_over;
+            tick.slowed = self.slowed;
+        }
+        *world.write_resource::<Random>() = self.random.clone();
+
+        // Create every entity first so links between them can be filled in afterwards.
+        let created = self.entities.iter()
+            .map(|saved| world.create_entity().with(saved.tile.clone()).build())
+            .collect::<Vec<_>>();
+
+        let mut heads = Vec::new();
+        let mut grid = world.write_resource::<Grid>();
+        let mut types = world.write::<Type>();
+        let mut players = world.write::<Player>();
+        let mut directions = world.write::<Direction>();
+        let mut extensions = world.write::<Extension>();
+        let mut parents = world.write::<Parent>();
+        let mut snakes = world.write::<Snake>();
+        let mut powers = world.write::<Powers>();
+        let mut expiries = world.write::<Expiry>();
+        let mut lives = world.write::<Live>();
+        let mut poisons = world.write::<Poison>();
+
+        for (saved, &entity) in self.entities.iter().zip(&created) {
+            if let Some(kind) = saved.kind {
+                types.insert(entity, kind);
+                if saved.placed {
+                    grid.insert(saved.tile.x as usize, saved.tile.y as usize, entity, kind);
+                }
+            }
+            if let Some(player) = saved.player {
+                players.insert(entity, player);
+            }
+            if let Some(ref direction) = saved.direction {
+                directions.insert(entity, direction.clone());
+            }
+            if let Some(extension) = saved.extension {
+                extensions.insert(entity, Extension(extension));
+            }
+            if let Some(parent) = saved.parent {
+                parents.insert(entity, Parent(created[parent]));
+            }
+            if let Some(ref snake) = saved.snake {
+                snakes.insert(entity, Snake {
+                    queued_direction: snake.queued_direction,
+                    length: snake.length,
+                    score: snake.score,
+                    end: snake.end.map(|end| created[end]),
+                });
+                heads.push((saved.player.map(|player| player.0).unwrap_or(0), entity));
+            }
+            if let Some(ref saved) = saved.powers {
+                powers.insert(entity, saved.clone());
+            }
+            if let Some(expiry) = saved.expiry {
+                expiries.insert(entity, expiry);
+            }
+            if let Some(live) = saved.live {
+                lives.insert(entity, live);
+            }
+            if saved.poison {
+                poisons.insert(entity, Poison);
+            }
+        }
+
+        heads.sort_by_key(|&(player, _)| player);
+        heads.into_iter().map(|(_, head)| head).collect()
+    }
+
+    pub fn save(&self, key: &str) -> io::Result<()> {
+        let path = path(key)?;
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory)?;
+        }
+
+        let file = BufWriter::new(File::create(path)?);
+        serde_json::to_writer(file, self).map_err(|error| io::Error::new(ErrorKind::Other, error))
+    }
+
+    pub fn load(key: &str) -> io::Result<Snapshot> {
+        let file = BufReader::new(File::open(path(key)?)?);
+        serde_json::from_reader(file).map_err(|error| io::Error::new(ErrorKind::InvalidData, error))
+    }
+}
+
+// Where the game saved under a key lives. Keys are plain names so they can't point outside the
+// save directory.
+pub fn path(key: &str) -> io::Result<PathBuf> {
+    let valid = !key.is_empty() && key.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        return Err(io::Error::new(ErrorKind::InvalidInput, format!("invalid save key {:?}", key)));
+    }
+
+    Ok(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join(SAVE_DIR).join(format!("{}.json", key)))
+}
@@ -0,0 +1,262 @@
+
+// Snakes are drawn piece by piece, with a head, straight body, corner and tail that are turned to
+// follow the way the snake bends.
+//
+// The pieces are drawn side by side on a sprite sheet, one texture for each colour in the theme,
+// and each piece is a square showing its part of the sheet. A piece faces right (towards +x) on the
+// sheet and is rotated into place, a corner joins the right and bottom edges of its tile. Gates and
+// conveyors are on the sheet too, as an arrow and a chevron turned the way they go.
+
+use amethyst::ecs::components::LocalTransform;
+use amethyst::renderer::VertexPosNormal;
+use specs::{Entities, Join, ReadStorage, System, WriteStorage};
+
+use body::Followers;
+use components::{Direction, Parent, Piece, Snake, Sprite, Tile, Type};
+use theme::Colour;
+
+// Half the width of the body, as a fraction of a tile.
+const BODY: f32 = 0.3;
+
+// Pixels across and down each piece on the sheet, with the pieces' edges at tenths of a tile falling
+// between pixels.
+pub const CELL: usize = 20;
+
+// Meshes showing the sheet, in the order their shapes sit along it from left to right.
+pub const SHAPES: [&'static str; 6] = ["head", "body", "corner", "tail", "arrow", "chevron"];
+
+// Size of the sheet in pixels.
+pub const SHEET: (usize, usize) = (CELL * 6, CELL);
+
+// Mesh each piece is drawn with.
+pub fn mesh(piece: Piece) -> &'static str {
+    match piece {
+        Piece::Head => "head",
+        Piece::Body => "body",
+        Piece::Corner => "corner",
+        Piece::Tail => "tail",
+    }
+}
+
+// The sheet with the pieces drawn in the colour of a texture.
+pub fn sheet(texture: &str) -> String {
+    format!("{}/sprites", texture)
+}
+
+// The texture a sheet takes its colour from, if a texture is a sheet.
+pub fn drawn_in(texture: &str) -> Option<&str> {
+    if texture.ends_with("/sprites") {
+        Some(&texture[..texture.len() - "/sprites".len()])
+    }
+    else {
+        None
+    }
+}
+
+// The triangles each shape on the sheet is made of, within a tile from -0.5 to 0.5 each way.
+fn outline(shape: &str) -> Vec<[[f32; 2]; 3]> {
+    match shape {
+        "head" => {
+            let mut head = quad(-0.5, -0.4, 0.1, 0.4);
+            head.push([[0.1, -0.4], [0.5, 0.0], [0.1, 0.4]]);
+            head
+        },
+        "body" => quad(-0.5, -BODY, 0.5, BODY),
+        "corner" => {
+            let mut corner = quad(-BODY, -BODY, 0.5, BODY);
+            corner.extend(quad(-BODY, BODY, BODY, 0.5));
+            corner
+        },
+        "tail" => vec![[[-0.4, 0.0], [0.5, -BODY], [0.5, BODY]]],
+        "arrow" => {
+            let mut arrow = quad(-0.4, -0.1, 0.05, 0.1);
+            arrow.push([[0.05, -0.35], [0.45, 0.0], [0.05, 0.35]]);
+            arrow
+        },
+        "chevron" => vec![
+            [[-0.3, -0.4], [-0.05, -0.4], [0.35, 0.0]],
+            [[0.35, 0.0], [0.1, 0.0], [-0.3, -0.4]],
+            [[-0.3, 0.4], [-0.05, 0.4], [0.35, 0.0]],
+            [[0.35, 0.0], [0.1, 0.0], [-0.3, 0.4]],
+        ],
+        _ => Vec::new(),
+    }
+}
+
+fn quad(left: f32, top: f32, right: f32, bottom: f32) -> Vec<[[f32; 2]; 3]> {
+    vec![
+        [[left, top], [right, top], [right, bottom]],
+        [[right, bottom], [left, bottom], [left, top]],
+    ]
+}
+
+// Whether a point is inside a triangle, whichever way round it winds.
+pub fn inside(triangle: &[[f32; 2]; 3], (x, y): (f32, f32)) -> bool {
+    let side = |a: [f32; 2], b: [f32; 2]| (b[0] - a[0]) * (y - a[1]) - (b[1] - a[1]) * (x - a[0]);
+    let sides = [
+        side(triangle[0], triangle[1]),
+        side(triangle[1], triangle[2]),
+        side(triangle[2], triangle[0]),
+    ];
+    sides.iter().all(|&side| side >= 0.0) || sides.iter().all(|&side| side <= 0.0)
+}
+
+// Which pixels of the sheet are part of a piece, a row at a time from the top.
+pub fn mask() -> Vec<bool> {
+    let outlines = SHAPES.iter().map(|shape| outline(shape)).collect::<Vec<_>>();
+    let mut mask = Vec::with_capacity(SHEET.0 * SHEET.1);
+    for row in 0..SHEET.1 {
+        for column in 0..SHEET.0 {
+            let centre = |pixel: usize| (pixel % CELL) as f32 / CELL as f32 + 0.5 / CELL as f32 - 0.5;
+            let point = (centre(column), centre(row));
+            mask.push(outlines[column / CELL].iter().any(|triangle| inside(triangle, point)));
+        }
+    }
+    mask
+}
+
+// The sheet's pixels with the pieces in a colour, and see-through elsewhere over the colour of
+// whatever they're drawn over.
+pub fn pixels(colour: Colour, background: Colour) -> Vec<[u8; 4]> {
+    let byte = |channel: f32| (channel.max(0.0).min(1.0) * 255.0).round() as u8;
+    mask().into_iter()
+        .map(|lit| {
+            let (colour, alpha) = if lit { (colour, colour[3]) } else { (background, 0.0) };
+            [byte(colour[0]), byte(colour[1]), byte(colour[2]), byte(alpha)]
+        })
+        .collect()
+}
+
+// A square mesh for every shape, showing its part of the sheet.
+pub fn meshes() -> Vec<(&'static str, Vec<VertexPosNormal>)> {
+    SHAPES.iter()
+        .enumerate()
+        .map(|(index, &shape)| {
+            let corner = |x: f32, y: f32| VertexPosNormal {
+                pos: [x, y, 0.],
+                normal: [0., 0., 1.],
+                tex_coord: [(index as f32 + x + 0.5) / SHAPES.len() as f32, y + 0.5],
+            };
+
+            // Counter-clockwise, the same as the square mesh.
+            let vertices = vec![
+                corner(-0.5, -0.5), corner(0.5, -0.5), corner(0.5, 0.5),
+                corner(0.5, 0.5), corner(-0.5, 0.5), corner(-0.5, -0.5),
+            ];
+            (shape, vertices)
+        })
+        .collect()
+}
+
+fn angle(towards: (i32, i32)) -> f32 {
+    (towards.1 as f32).atan2(towards.0 as f32)
+}
+
+// Rotation around the z axis, as a quaternion with the real part first.
+fn turn(local: &mut LocalTransform, angle: f32) {
+    let half = angle / 2.0;
+    local.rotation = [half.cos(), 0.0, 0.0, half.sin()];
+}
+
+// The way from one tile to the next, if they are next to each other or on the same tile. Segments
+// either side of a portal or a conveyor aren't.
+fn unit(from: &Tile, to: &Tile) -> Option<(i32, i32)> {
+    let (x, y) = (to.x - from.x, to.y - from.y);
+    if x.abs() + y.abs() <= 1 { Some((x, y)) } else { None }
+}
+
+// Which piece a segment is, from the way to the segments either side of it. The head uses the way
+// it is heading when there is nothing behind it yet.
+pub fn shape(towards_head: Option<(i32, i32)>, towards_tail: Option<(i32, i32)>, heading: (i8, i8)) -> Sprite {
+    let zero = (0, 0);
+    match (towards_head, towards_tail) {
+        (None, Some(back)) if back != zero => Sprite {
+            piece: Piece::Head,
+            angle: angle((-back.0, -back.1)),
+        },
+        (None, _) => Sprite {
+            piece: Piece::Head,
+            angle: angle((heading.0 as i32, heading.1 as i32)),
+        },
+        (Some(front), None) => Sprite {
+            piece: Piece::Tail,
+            angle: angle(front),
+        },
+        (Some(front), Some(back)) => {
+            // Segments that haven't spread out from one another yet are drawn straight.
+            if front == zero || back == zero || front == (-back.0, -back.1) || front == back {
+                let along = if front != zero { front } else { (-back.0, -back.1) };
+                Sprite {
+                    piece: Piece::Body,
+                    angle: angle(along),
+                }
+            }
+            else {
+                // The corner mesh turns from +x to +y, so start from whichever side comes first.
+                let start = if back == (-front.1, front.0) { front } else { back };
+                Sprite {
+                    piece: Piece::Corner,
+                    angle: angle(start),
+                }
+            }
+        },
+    }
+}
+
+// Works out which piece every snake segment is and turns it to match, and turns gates and conveyors
+// the way they go.
+#[derive(Default)]
+pub struct SpriteSystem;
+impl<'a> System<'a> for SpriteSystem {
+    type SystemData = (
+        // Resources
+        Entities<'a>,
+
+        // Components
+        ReadStorage<'a, Snake>,
+        ReadStorage<'a, Parent>,
+        ReadStorage<'a, Tile>,
+        ReadStorage<'a, Direction>,
+        ReadStorage<'a, Type>,
+        WriteStorage<'a, Sprite>,
+        WriteStorage<'a, LocalTransform>,
+    );
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, snakes, parents, tiles, directions, types, mut sprites, mut locals) = data;
+
+        for (kind, local) in (&types, &mut locals).join() {
+            match *kind {
+                Type::Gate(way) | Type::Conveyor(way) => turn(local, angle((way.0 as i32, way.1 as i32))),
+                _ => { },
+            }
+        }
+
+        let followers = Followers::new(&entities, &parents);
+
+        for (entity, tile) in (&*entities, &tiles).join() {
+            let parent = parents.get(entity).map(|parent| parent.0);
+            if snakes.get(entity).is_none() && parent.is_none() {
+                continue;
+            }
+
+            // Segments split by a portal point into the portal the parent went into, and out of the
+            // portal this segment came out of.
+            let heading_of = |segment| directions.get(segment)
+                .map(|direction| (direction.direction.0 as i32, direction.direction.1 as i32))
+                .unwrap_or((0, 0));
+            let towards = |other, through: (i32, i32)| tiles.get(other).map(|other| unit(tile, other).unwrap_or(through));
+            let heading = directions.get(entity).map(|direction| direction.direction).unwrap_or((1, 0));
+            let (own_x, own_y) = heading_of(entity);
+            let sprite = shape(
+                parent.and_then(|parent| towards(parent, heading_of(parent))),
+                followers.behind(entity).and_then(|follower| towards(follower, (-own_x, -own_y))),
+                heading,
+            );
+
+            if let Some(local) = locals.get_mut(entity) {
+                turn(local, sprite.angle);
+            }
+            sprites.insert(entity, sprite);
+        }
+    }
+}
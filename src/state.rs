@@ -1,145 +1,345 @@
 
-use amethyst::asset_manager::AssetManager;
-use amethyst::ecs::components::{Mesh, LocalTransform, Texture, Transform};
-use amethyst::renderer::{VertexPosNormal, Pipeline};
+use amethyst::asset_manager::{AssetManager, TextureLoadData};
+use amethyst::ecs::components::{Mesh, LocalTransform, Renderable, Texture, Transform};
+use amethyst::ecs::resources::{Camera, Projection, ScreenDimensions};
+use amethyst::renderer::{Layer, VertexPosNormal, Pipeline};
+use amethyst::renderer::pass::{Clear, DrawFlat};
 use amethyst::{Event, State, Trans, VirtualKeyCode, WindowEvent};
-use specs::{World, Join};
+use gfx::texture::{AaMode, Kind};
+use specs::{Entity, World, Join};
 
-use components::{Controls, Direction, Extension, Grid, Tile, Snake, Type, Tick};
+use std::time::Duration;
 
-pub struct GameState;
+use components::{Appearance, Board, Controls, Expiry, Flash, GridLine, Particle, Piece, Player, Poison, Powers, Sprite, Tick, Tile, Type};
+use effects::{Effects, FLASH_RATE};
+use food;
+use grid::Grid;
+use hud::Hud;
+use mode::GameMode;
+use power;
+use simulation;
+use snapshot::Snapshot;
+use sprite;
+use theme::Theme;
+use tick::{Clock, TICK_RATE};
+
+#[derive(Default)]
+pub struct GameState {
+    hud: Hud,
+
+    // The board's backdrop and grid lines.
+    scenery: Vec<Entity>,
+
+    themes: Vec<Theme>,
+    theme: usize,
+
+    // Name of the theme to start with.
+    starting_theme: Option<String>,
+}
+
+// Key the game is saved under with F5 and loaded from with F9.
+const QUICKSAVE: &'static str = "quicksave";
 
 impl GameState {
-    fn reset(&mut self, world: &mut World, assets: &mut AssetManager) {
-        // Reset any previous game state (in case game is restarting).
-        {
-            // Remove all previous entities.
-            for entity in world.entities().join()
-            {
-                world.entities().delete(entity);
-            }
-            // World::maintain will clean up the entities immediately.
-            // (Otherwise weird stuff happens?)
-            world.maintain();
+    pub fn new(theme: Option<String>) -> Self {
+        GameState {
+            starting_theme: theme,
+            .. GameState::default()
+        }
+    }
 
-            // Reset tick and grid state.
-            let mut tick = world.write_resource::<Tick>();
-            *tick = Tick::default();
+    fn reset(&mut self, world: &mut World) {
+        let heads = simulation::reset(world, ::PLAYERS);
+        self.control(world, heads);
+        world.write_resource::<Clock>().elapsed = Duration::default();
+    }
 
-            let mut grid = world.write_resource::<Grid>();
-            *grid = Grid::new(::GRID_X, ::GRID_Y);
+    fn save(&mut self, world: &mut World) {
+        match Snapshot::capture(world, ::PLAYERS).save(QUICKSAVE) {
+            Ok(()) => info!("Saved the game"),
+            Err(error) => error!("Failed to save the game: {}", error),
         }
+    }
 
-        let player1 = assets.create_renderable("square", "white", "white", "white", 1.0).unwrap();
+    fn load(&mut self, world: &mut World) {
+        match Snapshot::load(QUICKSAVE) {
+            Ok(snapshot) => {
+                let heads = snapshot.restore(world);
+                self.control(world, heads);
 
-        // Set up snakes
-        world.create_entity()
-            .with(player1.clone())
-            .with(LocalTransform::default())
-            .with(Transform::default())
-            .with(Tile { x: (::GRID_X / 2 - ::GRID_X / 4) as i32, y: ( 3 * ::GRID_Y / 4) as i32 })
-            .with(Snake { 
-                queued_direction: (0, -1),
-                length: 1,
-                end: None,
-            })
-            .with(Type::Kill)
-            .with(Direction::default())
-            .with(Controls {
-                left: VirtualKeyCode::A.into(),
-                right: VirtualKeyCode::D.into(),
-                up: VirtualKeyCode::W.into(),
-                down: VirtualKeyCode::S.into(),
-            })
-            .with(Extension(6)) // Start the snake off with 7 pieces (head + 6 tail pieces)
-            .build();
-
-        /*
-        // Extra snake if you want to add it.
-        let player2 = assets.create_renderable("square", "blue", "blue", "blue", 1.0).unwrap();
-        world.create_entity()
-            .with(player2.clone())
+                // Play time isn't saved, count it from the ticks played instead.
+                let ticks = world.read_resource::<Tick>().ticks;
+                world.write_resource::<Clock>().elapsed = Duration::from_millis(ticks * TICK_RATE / 1_000_000);
+                info!("Loaded the game");
+            },
+            Err(error) => error!("Failed to load the game: {}", error),
+        }
+    }
+
+    // Player 1 uses WASD, player 2 uses the arrow keys.
+    fn control(&mut self, world: &mut World, heads: Vec<Entity>) {
+        let mut controls = world.write::<Controls>();
+        for (player, head) in heads.into_iter().enumerate() {
+            controls.insert(head, match player {
+                0 => Controls {
+                    left: VirtualKeyCode::A.into(),
+                    right: VirtualKeyCode::D.into(),
+                    up: VirtualKeyCode::W.into(),
+                    down: VirtualKeyCode::S.into(),
+                },
+                _ => Controls {
+                    left: VirtualKeyCode::Left.into(),
+                    right: VirtualKeyCode::Right.into(),
+                    up: VirtualKeyCode::Up.into(),
+                    down: VirtualKeyCode::Down.into(),
+                },
+            });
+        }
+    }
+
+    // Move on to the next game mode, starting a new game in it.
+    fn switch_mode(&mut self, world: &mut World) {
+        let mode = world.read_resource::<GameMode>().next();
+        info!("Mode: {}", mode.name());
+        *world.write_resource::<GameMode>() = mode;
+        self.reset(world);
+    }
+
+    // Move on to the next theme, everything drawn gets redressed in its colours.
+    fn switch_theme(&mut self, world: &mut World, pipe: &mut Pipeline) {
+        self.theme = (self.theme + 1) % self.themes.len();
+        info!("Theme: {}", self.themes[self.theme].name);
+
+        pipe.layers.clear();
+        pipe.layers.push(layer(&self.themes[self.theme]));
+        for &entity in &self.scenery {
+            world.entities().delete(entity);
+        }
+        self.scenery.clear();
+    }
+
+    // Draw the board's backdrop and grid lines, restarting the game deletes them along with
+    // everything else.
+    fn scenery(&mut self, world: &mut World, assets: &mut AssetManager) {
+        let alive = {
+            let entities = world.entities();
+            !self.scenery.is_empty() && self.scenery.iter().all(|&entity| entities.is_alive(entity))
+        };
+        if alive {
+            return;
+        }
+
+        self.scenery = build_scenery(world, &self.themes[self.theme]);
+        for &entity in &self.scenery {
+            let appearance = world.read::<Appearance>().get(entity).cloned();
+            if let Some(appearance) = appearance {
+                render(world, assets, entity, &appearance);
+            }
+        }
+    }
+
+    // Give anything the game rules placed on the grid something to be drawn with, swapping it out
+    // whenever a snake segment turns into a different piece.
+    fn dress(&mut self, world: &mut World, assets: &mut AssetManager) {
+        let changed = appearances(world, &self.themes[self.theme]);
+        for (entity, appearance) in changed {
+            render(world, assets, entity, &appearance);
+            wear(world, entity, appearance);
+        }
+    }
+}
+
+// Point the camera straight at the window, one unit to a pixel with y going down the screen.
+pub fn camera(world: &mut World) {
+    let dim = world.read_resource::<ScreenDimensions>();
+    let mut camera = world.write_resource::<Camera>();
+    let eye = [0., 0., 1.0];
+    let target = [0., 0., 0.];
+    let up = [0., 1., 0.];
+
+    let proj = Projection::Orthographic {
+        left: 0.0,
+        right: dim.w,
+        bottom: dim.h,
+        top: 0.0,
+        near: -1.0,
+        far: 1.0,
+    };
+
+    camera.proj = proj;
+    camera.eye = eye;
+    camera.target = target;
+    camera.up = up;
+}
+
+// Every mesh things are drawn with, by name.
+pub fn meshes() -> Vec<(&'static str, Vec<VertexPosNormal>)> {
+    let mut meshes = vec![("square", gen_rectangle(1.0, 1.0))];
+    meshes.extend(sprite::meshes());
+    meshes
+}
+
+// Create the board's backdrop and a line between each of its rows and columns, in a theme's colours.
+pub fn build_scenery(world: &mut World, theme: &Theme) -> Vec<Entity> {
+    let board = Appearance { mesh: "square", colour: theme.texture("board") };
+    let mut scenery = vec![world.create_entity()
+        .with(board)
+        .with(LocalTransform::default())
+        .with(Transform::default())
+        .with(Board)
+        .build()];
+
+    let (width, height) = world.read_resource::<Grid>().len;
+    let lines = (0..width + 1).map(|index| GridLine { vertical: true, index: index })
+        .chain((0..height + 1).map(|index| GridLine { vertical: false, index: index }));
+    for line in lines {
+        scenery.push(world.create_entity()
+            .with(Appearance { mesh: "square", colour: theme.texture("grid_lines") })
             .with(LocalTransform::default())
             .with(Transform::default())
-            .with(Tile { x: (::GRID_X / 2 + ::GRID_X / 4) as i32, y: ( 3 * ::GRID_Y / 4) as i32 })
-            .with(Snake { 
-                queued_direction: (0, -1),
-                length: 1,
-                end: None,
-            })
-            .with(Type::Kill)
-            .with(Direction::default())
-            .with(Controls {
-                left: VirtualKeyCode::Left.into(),
-                right: VirtualKeyCode::Right.into(),
-                up: VirtualKeyCode::Up.into(),
-                down: VirtualKeyCode::Down.into(),
-            })
-            .with(Extension(200)) // Start the snake off with 6 pieces
-            .build();
-        */
+            .with(line)
+            .build());
     }
+    scenery
 }
 
-impl State for GameState {
-    fn on_start(&mut self, world: &mut World, assets: &mut AssetManager, pipe: &mut Pipeline) {
-        use amethyst::ecs::resources::{Camera, InputHandler, Projection, ScreenDimensions};
-        use amethyst::renderer::Layer;
-        use amethyst::renderer::pass::{Clear, DrawFlat};
+// How everything on the board and every particle should look in a theme, for whatever doesn't look
+// that way already.
+pub fn appearances(world: &World, theme: &Theme) -> Vec<(Entity, Appearance)> {
+    let entities = world.entities();
+    let tiles = world.read::<Tile>();
+    let types = world.read::<Type>();
+    let players = world.read::<Player>();
+    let sprites = world.read::<Sprite>();
+    let appearances = world.read::<Appearance>();
 
-        let layer = Layer::new("main",
-                               vec![Clear::new([0.0, 0.0, 0.0, 1.0]),
-                                    DrawFlat::new("main", "main")]);
+    let powers = world.read::<Powers>();
+    let expiries = world.read::<Expiry>();
+    let poisons = world.read::<Poison>();
+    let ticks = world.read_resource::<Tick>().ticks;
 
-        pipe.layers.push(layer);
+    let flashes = world.read::<Flash>();
+    let particles = world.read::<Particle>();
 
-        // Camera
-        {
-            let dim = world.read_resource::<ScreenDimensions>();
-            let mut camera = world.write_resource::<Camera>();
-            let eye = [0., 0., 1.0];
-            let target = [0., 0., 0.];
-            let up = [0., 1., 0.];
-
-            let proj = Projection::Orthographic {
-                left: 0.0,
-                right: dim.w,
-                bottom: dim.h,
-                top: 0.0,
-                near: -1.0,
-                far: 1.0,
+    let things = (&*entities, &tiles, &types).join()
+        .map(|(entity, _, kind)| {
+            let player = players.get(entity).map(|player| player.0).unwrap_or(0);
+            let mut appearance = match (*kind, sprites.get(entity)) {
+                (Type::Snake, Some(sprite)) => Appearance {
+                    mesh: sprite::mesh(sprite.piece),
+                    colour: if sprite.piece == Piece::Head { theme.head(player) } else { theme.body(player) },
+                },
+                (Type::Snake, None) => Appearance { mesh: "square", colour: theme.body(player) },
+                // Expiring food blinks out to the board colour.
+                (Type::Food, _) => match (expiries.get(entity).map(food::visible), poisons.get(entity)) {
+                    (Some(false), _) => Appearance { mesh: "square", colour: theme.texture("board") },
+                    (_, Some(_)) => Appearance { mesh: "square", colour: theme.texture("poison") },
+                    _ => Appearance { mesh: "square", colour: theme.texture("food") },
+                },
+                (Type::Wall, _) => Appearance { mesh: "square", colour: theme.texture("walls") },
+                (Type::PowerUp(power), _) => Appearance { mesh: "square", colour: theme.texture(power::colour(power)) },
+                (Type::Portal { .. }, _) => Appearance { mesh: "square", colour: theme.texture("portal") },
+                (Type::Gate(_), _) => Appearance { mesh: "arrow", colour: theme.texture("gate") },
+                (Type::Ice, _) => Appearance { mesh: "square", colour: theme.texture("ice") },
+                (Type::Conveyor(_), _) => Appearance { mesh: "chevron", colour: theme.texture("conveyor") },
             };
 
-            camera.proj = proj;
-            camera.eye = eye;
-            camera.target = target;
-            camera.up = up;
+            // Heads take the colour of their first power, blinking when it's about to run out.
+            if let Some(&(power, remaining)) = powers.get(entity).and_then(|powers| powers.0.first()) {
+                if remaining > power::WARNING || ticks % 2 == 0 {
+                    appearance.colour = theme.texture(power::colour(power));
+                }
+            }
+
+            // Flashing swaps to the HUD colour every other beat.
+            if let Some(flash) = flashes.get(entity) {
+                if (flash.remaining * FLASH_RATE) as u32 % 2 == 1 {
+                    appearance.colour = theme.texture("hud");
+                }
+            }
+
+            // Shapes on the sprite sheet are shown in whichever colour they ended up.
+            if sprite::SHAPES.contains(&appearance.mesh) {
+                appearance.colour = sprite::sheet(&appearance.colour);
+            }
+            (entity, appearance)
+        });
+    let specks = (&*entities, &particles).join()
+        .map(|(entity, particle)| (entity, Appearance { mesh: "square", colour: theme.texture(particle.colour) }));
+
+    things.chain(specks)
+        .filter(|&(entity, ref appearance)| appearances.get(entity) != Some(appearance))
+        .collect::<Vec<_>>()
+}
+
+// Start drawing something as it looks now, putting it where it belongs on the next frame.
+pub fn wear(world: &mut World, entity: Entity, appearance: Appearance) {
+    world.write::<Appearance>().insert(entity, appearance);
+    if world.read::<LocalTransform>().get(entity).is_none() {
+        world.write::<LocalTransform>().insert(entity, LocalTransform::default());
+        world.write::<Transform>().insert(entity, Transform::default());
+    }
+}
+
+// Make something a renderable for how it looks.
+fn render(world: &mut World, assets: &mut AssetManager, entity: Entity, appearance: &Appearance) {
+    let renderable = {
+        let colour = &appearance.colour;
+        assets.create_renderable(appearance.mesh, colour, colour, colour, 1.0).unwrap()
+    };
+    world.write::<Renderable>().insert(entity, renderable);
+}
+
+impl State for GameState {
+    fn on_start(&mut self, world: &mut World, assets: &mut AssetManager, pipe: &mut Pipeline) {
+        use amethyst::ecs::resources::InputHandler;
+
+        self.themes = Theme::load_all(format!("{}/resources/themes", env!("CARGO_MANIFEST_DIR")));
+        if let Some(ref name) = self.starting_theme {
+            match self.themes.iter().position(|theme| theme.name == *name) {
+                Some(index) => self.theme = index,
+                None => warn!("No theme called {}", name),
+            }
         }
 
+        pipe.layers.push(layer(&self.themes[self.theme]));
+
+        camera(world);
+
         // Generate a square mesh
         assets.register_asset::<Mesh>();
         assets.register_asset::<Texture>();
 
-        // Textures
-        assets.load_asset_from_data::<Texture, [f32; 4]>("white", [1.0, 1.0, 1.0, 1.0]);
-        assets.load_asset_from_data::<Texture, [f32; 4]>("blue", [0.0, 0.0, 1.0, 1.0]);
-        assets.load_asset_from_data::<Texture, [f32; 4]>("red", [1.0, 0.0, 0.0, 1.0]);
+        // Textures for every theme, so switching between them is instant.
+        for theme in &self.themes {
+            for (name, colour) in theme.textures() {
+                assets.load_asset_from_data::<Texture, [f32; 4]>(&name, colour);
 
-        // Square vertices/mesh/polygon
-        let square_verts = gen_rectangle(1.0, 1.0);
-        assets.load_asset_from_data::<Mesh, Vec<VertexPosNormal>>("square", square_verts);
+                let pixels = sprite::pixels(colour, theme.board);
+                let sheet = TextureLoadData {
+                    kind: Kind::D2(sprite::SHEET.0 as u16, sprite::SHEET.1 as u16, AaMode::Single),
+                    raw: &[&pixels],
+                };
+                assets.load_asset_from_data::<Texture, TextureLoadData>(&sprite::sheet(&name), sheet);
+            }
+        }
+
+        // Squares, and squares showing a piece of the sprite sheet
+        for (name, vertices) in meshes() {
+            assets.load_asset_from_data::<Mesh, Vec<VertexPosNormal>>(name, vertices);
+        }
 
         // Add all resources
         world.add_resource::<InputHandler>(InputHandler::new());
 
-        self.reset(world, assets);
+        self.reset(world);
     }
 
     fn handle_events(&mut self,
                      events: &[WindowEvent],
                      world: &mut World,
-                     assets: &mut AssetManager,
-                     _: &mut Pipeline)
+                     _: &mut AssetManager,
+                     pipe: &mut Pipeline)
                      -> Trans {
         use amethyst::ElementState;
         use amethyst::ecs::resources::InputHandler;
@@ -149,11 +349,36 @@ impl State for GameState {
             input.update(events);
         }
 
-        // Press R to restart game.
+        // Press R to restart game, F5 to save it, F9 to load it again, H to show or hide the HUD,
+        // M to switch game mode, T to switch theme and 1 to 4 to switch particles, screen shake, flashing and sound on or off.
         for e in events {
             match **e {
                 Event::KeyboardInput(ElementState::Pressed, _, Some(VirtualKeyCode::R)) =>
-                    self.reset(world, assets),
+                    self.reset(world),
+                Event::KeyboardInput(ElementState::Pressed, _, Some(VirtualKeyCode::F5)) =>
+                    self.save(world),
+                Event::KeyboardInput(ElementState::Pressed, _, Some(VirtualKeyCode::F9)) =>
+                    self.load(world),
+                Event::KeyboardInput(ElementState::Pressed, _, Some(VirtualKeyCode::H)) =>
+                    self.hud.toggle(),
+                Event::KeyboardInput(ElementState::Pressed, _, Some(VirtualKeyCode::M)) =>
+                    self.switch_mode(world),
+                Event::KeyboardInput(ElementState::Pressed, _, Some(VirtualKeyCode::T)) =>
+                    self.switch_theme(world, pipe),
+                Event::KeyboardInput(ElementState::Pressed, _, Some(key @ VirtualKeyCode::Key1)) |
+                Event::KeyboardInput(ElementState::Pressed, _, Some(key @ VirtualKeyCode::Key2)) |
+                Event::KeyboardInput(ElementState::Pressed, _, Some(key @ VirtualKeyCode::Key3)) |
+                Event::KeyboardInput(ElementState::Pressed, _, Some(key @ VirtualKeyCode::Key4)) => {
+                    let mut effects = world.write_resource::<Effects>();
+                    let (name, enabled) = match key {
+                        VirtualKeyCode::Key1 => ("Particles", &mut effects.particles),
+                        VirtualKeyCode::Key2 => ("Screen shake", &mut effects.shake),
+                        VirtualKeyCode::Key3 => ("Flashing", &mut effects.flash),
+                        _ => ("Sound", &mut effects.sound),
+                    };
+                    *enabled = !*enabled;
+                    info!("{} {}", name, if *enabled { "on" } else { "off" });
+                },
                 Event::KeyboardInput(_, _, Some(VirtualKeyCode::Escape)) |
                 Event::Closed => return Trans::Quit,
                 _ => (),
@@ -164,49 +389,23 @@ impl State for GameState {
     }
 
     fn update(&mut self, world: &mut World, assets: &mut AssetManager, _: &mut Pipeline) -> Trans {
-        use components::{Tick, Grid};
-
-        // Spawn a new food every 50 ticks.
-        let pos =
-        {
-            let ticks = world.read_resource::<Tick>();
-            
-            if ticks.ticks % 50 == 0 && ticks.ticked {
-                let grid = world.read_resource::<Grid>();
-            
-                // TODO: Get a random empty position for the food.
-                use rand::{self, Rng};
-                let mut rng = rand::thread_rng();
-                let x: i32 = rng.gen_range(0, grid.len.0 as i32);
-                let y: i32 = rng.gen_range(0, grid.len.1 as i32);                
-                Some((x, y))
-            }
-            else {
-                None
-            }
-        };
+        self.scenery(world, assets);
+        self.dress(world, assets);
 
-        if let Some(position) = pos {
-            let food = assets.create_renderable("square", "red", "red", "red", 1.0).unwrap();
-
-            world.create_entity()
-                .with(food)
-                .with(LocalTransform::default())
-                .with(Transform::default())
-                .with(Direction {
-                    direction: (0, 0),
-                    previous: None,
-                })
-                .with(Tile { x: position.0 as i32, y: position.1 as i32 })
-                .with(Type::Eat)
-                .build();
-        }
-        
+        let hud = self.themes[self.theme].texture("hud");
+        self.hud.draw(world, assets, &hud);
         Trans::None
     }
 }
 
-// Generate a suqare from vertices
+// Clears to the theme's background then draws everything.
+fn layer(theme: &Theme) -> Layer {
+    Layer::new("main",
+               vec![Clear::new(theme.background),
+                    DrawFlat::new("main", "main")])
+}
+
+// Generate a square from vertices
 fn gen_rectangle(w: f32, h: f32) -> Vec<VertexPosNormal> {
     let data: Vec<VertexPosNormal> = vec![VertexPosNormal {
                                               pos: [-w / 2., -h / 2., 0.],
@@ -231,12 +430,12 @@ fn gen_rectangle(w: f32, h: f32) -> Vec<VertexPosNormal> {
                                           VertexPosNormal {
                                               pos: [-w / 2., h / 2., 0.],
                                               normal: [0., 0., 1.],
-                                              tex_coord: [1., 1.],
+                                              tex_coord: [0., 1.],
                                           },
                                           VertexPosNormal {
                                               pos: [-w / 2., -h / 2., 0.],
                                               normal: [0., 0., 1.],
-                                              tex_coord: [1., 1.],
+                                              tex_coord: [0., 0.],
                                           }];
     data
 }
@@ -0,0 +1,249 @@
+
+// Live game state for spectators, as one line of JSON per tick.
+//
+// A stream target is either `tcp:<address>` to serve frames to any spectator that connects, `-` for
+// standard output, or a path to a file that frames get appended to.
+
+use std::fs::OpenOptions;
+use std::io::{self, LineWriter, Write};
+use std::net::{TcpListener, TcpStream};
+
+use serde_json;
+use specs::{Entities, Fetch, Join, ReadStorage, System, World};
+
+use arena::Arena;
+use body::Followers;
+use components::{Expiry, Parent, Player, Poison, Power, Powers, Snake, Tick, Tile, Type};
+use food;
+use grid::Grid;
+use mode::{self, GameMode};
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SnakeFrame {
+    pub player: usize,
+    pub length: u32,
+    pub score: u32,
+
+    // Positions of each segment, starting at the head.
+    pub body: Vec<(i32, i32)>,
+
+    // Powers the snake has with how many ticks they have left.
+    #[serde(default)]
+    pub powers: Vec<(Power, u32)>,
+}
+
+// Everything a spectator needs to draw one tick of the game.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Frame {
+    pub tick: u64,
+    pub game_over: bool,
+    pub grid: (usize, usize),
+    pub snakes: Vec<SnakeFrame>,
+    pub food: Vec<(i32, i32)>,
+    pub walls: Vec<(i32, i32)>,
+
+    #[serde(default)]
+    pub power_ups: Vec<((i32, i32), Power)>,
+    #[serde(default)]
+    pub poison: Vec<(i32, i32)>,
+
+    #[serde(default)]
+    pub mode: GameMode,
+
+    // Ticks left before the time runs out, in modes with a time limit.
+    #[serde(default)]
+    pub time_left: Option<u64>,
+
+    #[serde(default)]
+    pub portals: Vec<(i32, i32)>,
+
+    // Gates and conveyors with the way they point.
+    #[serde(default)]
+    pub gates: Vec<((i32, i32), (i8, i8))>,
+    #[serde(default)]
+    pub ice: Vec<(i32, i32)>,
+    #[serde(default)]
+    pub conveyors: Vec<((i32, i32), (i8, i8))>,
+
+    // Positions of the arena's next ring while it's about to close.
+    #[serde(default)]
+    pub closing: Vec<(i32, i32)>,
+}
+impl Frame {
+    pub fn capture(world: &World) -> Frame {
+        Frame::build(
+            &world.read_resource::<Tick>(),
+            &world.read_resource::<Grid>(),
+            &world.read_resource::<GameMode>(),
+            &world.read_resource::<Arena>(),
+            &world.entities(),
+            &world.read::<Snake>(),
+            &world.read::<Player>(),
+            &world.read::<Parent>(),
+            &world.read::<Tile>(),
+            &world.read::<Type>(),
+            &world.read::<Powers>(),
+            &world.read::<Expiry>(),
+            &world.read::<Poison>(),
+        )
+    }
+
+    fn build(
+        tick: &Tick,
+        grid: &Grid,
+        mode: &GameMode,
+        arena: &Arena,
+        entities: &Entities,
+        snakes: &ReadStorage<Snake>,
+        players: &ReadStorage<Player>,
+        parents: &ReadStorage<Parent>,
+        tiles: &ReadStorage<Tile>,
+        types: &ReadStorage<Type>,
+        powers: &ReadStorage<Powers>,
+        expiries: &ReadStorage<Expiry>,
+        poisons: &ReadStorage<Poison>,
+    ) -> Frame {
+        let followers = Followers::new(entities, parents);
+
+        let mut frame = Frame {
+            tick: tick.ticks,
+            game_over: tick.game_over,
+            grid: grid.len,
+            mode: *mode,
+            time_left: mode::remaining(*mode, tick),
+            closing: arena.closing(tick, grid.len).into_iter().map(|(x, y)| (x as i32, y as i32)).collect(),
+            .. Frame::default()
+        };
+
+        for (head, snake) in (&**entities, snakes).join() {
+            let body = followers.body(head, snake.end).into_iter()
+                .filter_map(|segment| tiles.get(segment).map(|tile| (tile.x, tile.y)))
+                .collect::<Vec<_>>();
+
+            frame.snakes.push(SnakeFrame {
+                player: players.get(head).map(|player| player.0).unwrap_or(0),
+                length: snake.length,
+                score: snake.score,
+                body: body,
+                powers: powers.get(head).map(|powers| powers.0.clone()).unwrap_or_default(),
+            });
+        }
+        frame.snakes.sort_by_key(|snake| snake.player);
+
+        for (entity, tile, kind) in (&**entities, tiles, types).join() {
+            match *kind {
+                // Expiring food blinks for spectators too.
+                Type::Food => if expiries.get(entity).map(food::visible).unwrap_or(true) {
+                    if poisons.get(entity).is_some() {
+                        frame.poison.push((tile.x, tile.y));
+                    }
+                    else {
+                        frame.food.push((tile.x, tile.y));
+                    }
+                },
+                Type::Wall => frame.walls.push((tile.x, tile.y)),
+                Type::PowerUp(power) => frame.power_ups.push(((tile.x, tile.y), power)),
+                Type::Portal { .. } => frame.portals.push((tile.x, tile.y)),
+                Type::Gate(way) => frame.gates.push(((tile.x, tile.y), way)),
+                Type::Ice => frame.ice.push((tile.x, tile.y)),
+                Type::Conveyor(way) => frame.conveyors.push(((tile.x, tile.y), way)),
+                Type::Snake => { },
+            }
+        }
+
+        frame
+    }
+}
+
+// Where frames get sent.
+pub enum Stream {
+    Writer(Box<Write + Send + Sync>),
+    Listener {
+        listener: TcpListener,
+        spectators: Vec<TcpStream>,
+    },
+}
+impl Stream {
+    pub fn open(target: &str) -> io::Result<Stream> {
+        if target.starts_with("tcp:") {
+            let listener = TcpListener::bind(&target[4..])?;
+            listener.set_nonblocking(true)?;
+            Ok(Stream::Listener {
+                listener: listener,
+                spectators: Vec::new(),
+            })
+        }
+        else if target == "-" {
+            Ok(Stream::Writer(Box::new(io::stdout())))
+        }
+        else {
+            let file = OpenOptions::new().create(true).append(true).open(target)?;
+            Ok(Stream::Writer(Box::new(LineWriter::new(file))))
+        }
+    }
+
+    pub fn send(&mut self, frame: &Frame) -> io::Result<()> {
+        let mut line = serde_json::to_string(frame)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+        line.push('\n');
+
+        match *self {
+            Stream::Writer(ref mut writer) => {
+                writer.write_all(line.as_bytes())?;
+                writer.flush()
+            },
+            Stream::Listener { ref listener, ref mut spectators } => {
+                while let Ok((spectator, _)) = listener.accept() {
+                    // Never let a slow spectator hold up the game.
+                    spectator.set_nonblocking(true)?;
+                    spectators.push(spectator);
+                }
+
+                // Spectators that can't keep up or went away are dropped.
+                spectators.retain(|mut spectator| spectator.write_all(line.as_bytes()).is_ok());
+                Ok(())
+            },
+        }
+    }
+}
+
+// Sends a frame to a stream after every tick.
+pub struct StreamSystem(pub Option<Stream>);
+impl<'a> System<'a> for StreamSystem {
+    type SystemData = (
+        // Resources
+        Entities<'a>,
+        Fetch<'a, Tick>,
+        Fetch<'a, Grid>,
+        Fetch<'a, GameMode>,
+        Fetch<'a, Arena>,
+
+        // Components
+        ReadStorage<'a, Snake>,
+        ReadStorage<'a, Player>,
+        ReadStorage<'a, Parent>,
+        ReadStorage<'a, Tile>,
+        ReadStorage<'a, Type>,
+        ReadStorage<'a, Powers>,
+        ReadStorage<'a, Expiry>,
+        ReadStorage<'a, Poison>,
+    );
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, tick, grid, mode, arena, snakes, players, parents, tiles, types, powers, expiries, poisons) = data;
+
+        if !tick.ticked {
+            return;
+        }
+
+        let frame = Frame::build(&tick, &grid, &mode, &arena, &entities, &snakes, &players, &parents, &tiles, &types, &powers, &expiries, &poisons);
+        let result = match self.0 {
+            Some(ref mut stream) => stream.send(&frame),
+            None => return,
+        };
+
+        if let Err(error) = result {
+            warn!("Stopped streaming: {}", error);
+            self.0 = None;
+        }
+    }
+}
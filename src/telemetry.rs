@@ -0,0 +1,167 @@
+
+// A record of every game played, appended to a file as one line of JSON per game so difficulty and
+// performance can be looked at across many sessions.
+
+use std::fs::OpenOptions;
+use std::io::{self, LineWriter, Write};
+use std::path::Path;
+use std::time::Instant;
+
+use serde_json;
+use specs::{Fetch, Join, ReadStorage, System};
+
+use components::{Snake, Tick, Type};
+use event::{Cause, Events, GameEvent, Reader};
+use mode::GameMode;
+use random::Random;
+use tick::Clock;
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Record {
+    pub seed: u64,
+    pub players: usize,
+    pub mode: String,
+
+    // Seconds from the first tick until the game ended.
+    pub duration: f64,
+    pub ticks: u64,
+    // Food that grew a snake, and poison that shrank one.
+    pub foods_eaten: u32,
+    pub poison_eaten: u32,
+    pub max_length: u32,
+
+    // What killed the first snake to die, none if the game was restarted before it ended.
+    pub cause_of_death: Option<String>,
+
+    // Only known when the game is drawn in a window.
+    pub average_fps: Option<f32>,
+
+    // How many times a player changed direction.
+    pub inputs: u32,
+}
+
+fn describe(cause: Cause) -> String {
+    match cause {
+        Cause::Edge => "edge".to_owned(),
+        Cause::Shrunk => "shrunk".to_owned(),
+        Cause::Crushed => "crushed".to_owned(),
+        Cause::Hit(Type::Gate(_)) => "gate".to_owned(),
+        Cause::Hit(kind) => format!("{:?}", kind).to_lowercase(),
+    }
+}
+
+// Keeps track of the game being played and writes its record once it's over.
+#[derive(Default)]
+pub struct TelemetrySystem {
+    writer: Option<Box<Write + Send + Sync>>,
+    reader: Reader,
+
+    record: Record,
+    started: Option<Instant>,
+    frames: u32,
+    fps: f64,
+    last_ticks: u64,
+}
+impl TelemetrySystem {
+    // Append records to a file.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<TelemetrySystem> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(TelemetrySystem {
+            writer: Some(Box::new(LineWriter::new(file))),
+            .. TelemetrySystem::default()
+        })
+    }
+
+    // Write out the game so far and start over.
+    fn finish(&mut self, ticks: u64) {
+        if let Some(started) = self.started.take() {
+            let elapsed = started.elapsed();
+            self.record.duration = elapsed.as_secs() as f64 + elapsed.subsec_nanos() as f64 / 1_000_000_000.0;
+            self.record.ticks = ticks;
+            if self.frames > 0 {
+                self.record.average_fps = Some((self.fps / self.frames as f64) as f32);
+            }
+
+            let line = serde_json::to_string(&self.record)
+                .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error));
+            let result = match self.writer {
+                Some(ref mut writer) => line.and_then(|line| writeln!(writer, "{}", line)),
+                None => Ok(()),
+            };
+
+            match result {
+                Ok(()) => debug!("Recorded game: {:?}", self.record),
+                Err(error) => {
+                    error!("Stopped recording telemetry: {}", error);
+                    self.writer = None;
+                },
+            }
+        }
+
+        self.record = Record::default();
+        self.frames = 0;
+        self.fps = 0.0;
+    }
+}
+impl<'a> System<'a> for TelemetrySystem {
+    type SystemData = (
+        // Resources
+        Fetch<'a, Events>,
+        Fetch<'a, Tick>,
+        Fetch<'a, Random>,
+        Fetch<'a, Clock>,
+        Fetch<'a, GameMode>,
+
+        // Components
+        ReadStorage<'a, Snake>,
+    );
+    fn run(&mut self, (events, tick, random, clock, mode, snakes): Self::SystemData) {
+        // The tick count going backwards means the game was restarted before it ended.
+        if tick.ticks < self.last_ticks {
+            let ticks = self.last_ticks;
+            self.finish(ticks);
+        }
+        self.last_ticks = tick.ticks;
+
+        if tick.game_over && self.started.is_none() {
+            // Already recorded, just keep up with the events.
+            events.read(&mut self.reader);
+            return;
+        }
+
+        if self.started.is_none() {
+            self.started = Some(Instant::now());
+            self.record.seed = random.seed;
+            self.record.mode = mode.name().to_owned();
+        }
+
+        for event in events.read(&mut self.reader) {
+            match event {
+                GameEvent::SnakeSteered { .. } => self.record.inputs += 1,
+                GameEvent::FoodSwallowed { poison: false, .. } => self.record.foods_eaten += 1,
+                GameEvent::FoodSwallowed { poison: true, .. } => self.record.poison_eaten += 1,
+                GameEvent::SnakeDied { cause, .. } => {
+                    if self.record.cause_of_death.is_none() {
+                        self.record.cause_of_death = Some(describe(cause));
+                    }
+                },
+                _ => { },
+            }
+        }
+
+        self.record.players = snakes.join().count().max(self.record.players);
+        for snake in snakes.join() {
+            self.record.max_length = self.record.max_length.max(snake.length);
+        }
+
+        if clock.fps > 0.0 {
+            self.frames += 1;
+            self.fps += clock.fps as f64;
+        }
+
+        if tick.game_over {
+            info!("Game over after {} ticks", tick.ticks);
+            self.finish(tick.ticks);
+        }
+    }
+}
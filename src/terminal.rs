@@ -0,0 +1,143 @@
+
+use specs::World;
+
+use components::Power;
+use mode::GameMode;
+use stream::Frame;
+use tick::TICK_RATE;
+
+// Characters used for each player's snake, head and body.
+pub const HEADS: [char; 4] = ['@', '&', '%', '$'];
+pub const BODIES: [char; 4] = ['o', '+', 'x', '='];
+
+pub const FOOD: char = '*';
+pub const POISON: char = '!';
+pub const WALL: char = '#';
+pub const PORTAL: char = 'O';
+pub const ICE: char = '~';
+pub const EMPTY: char = ' ';
+pub const CLOSING: char = '.';
+
+// Character for each power-up on the board.
+pub fn power_up(power: Power) -> char {
+    match power {
+        Power::Ghost => 'G',
+        Power::SlowTime => 'T',
+        Power::Magnet => 'M',
+        Power::Shield => 'S',
+        Power::Reverse => 'R',
+    }
+}
+
+// Characters for gates and conveyors pointing up, right, down and left.
+pub const GATES: [char; 4] = ['^', '>', 'v', '<'];
+pub const CONVEYORS: [char; 4] = ['A', '}', 'V', '{'];
+
+// Character for something on the board pointing a way.
+pub fn pointing(glyphs: &[char; 4], way: (i8, i8)) -> char {
+    match way {
+        (0, -1) => glyphs[0],
+        (1, 0) => glyphs[1],
+        (0, 1) => glyphs[2],
+        _ => glyphs[3],
+    }
+}
+
+// Draw the world as lines of text.
+pub fn render(world: &World) -> Vec<String> {
+    draw(&Frame::capture(world))
+}
+
+// Draw a frame as lines of text, one character per cell with a border around the board.
+pub fn draw(frame: &Frame) -> Vec<String> {
+    let (width, height) = frame.grid;
+    let mut cells = vec![vec![EMPTY; width]; height];
+    {
+        let mut put = |(x, y): (i32, i32), c: char| {
+            if x >= 0 && y >= 0 && (x as usize) < width && (y as usize) < height {
+                cells[y as usize][x as usize] = c;
+            }
+        };
+
+        for &tile in &frame.closing {
+            put(tile, CLOSING);
+        }
+        for &wall in &frame.walls {
+            put(wall, WALL);
+        }
+        for &portal in &frame.portals {
+            put(portal, PORTAL);
+        }
+        for &(gate, way) in &frame.gates {
+            put(gate, pointing(&GATES, way));
+        }
+        for &ice in &frame.ice {
+            put(ice, ICE);
+        }
+        for &(conveyor, way) in &frame.conveyors {
+            put(conveyor, pointing(&CONVEYORS, way));
+        }
+        for &food in &frame.food {
+            put(food, FOOD);
+        }
+        for &poison in &frame.poison {
+            put(poison, POISON);
+        }
+        for &(tile, power) in &frame.power_ups {
+            put(tile, power_up(power));
+        }
+        for snake in &frame.snakes {
+            // Draw from the tail up so the head ends up on top.
+            for (index, &segment) in snake.body.iter().enumerate().rev() {
+                let glyphs = if index == 0 { &HEADS } else { &BODIES };
+                put(segment, glyphs[snake.player % glyphs.len()]);
+            }
+        }
+    }
+
+    let border = format!("+{}+", "-".repeat(width));
+    let mut lines = Vec::with_capacity(height + 2);
+    lines.push(border.clone());
+    for row in cells {
+        lines.push(format!("|{}|", row.into_iter().collect::<String>()));
+    }
+    lines.push(border);
+    lines
+}
+
+// The mode being played and, if it has a time limit, how long is left.
+pub fn status(frame: &Frame) -> String {
+    let name = match frame.mode {
+        GameMode::Classic => "Classic",
+        GameMode::TimeAttack => "Time attack",
+        GameMode::Survival => "Survival",
+        GameMode::BattleRoyale => "Battle royale",
+    };
+
+    match frame.time_left {
+        Some(ticks) => {
+            let seconds = ticks * TICK_RATE / 1_000_000_000;
+            format!("{}  {}:{:02} left", name, seconds / 60, seconds % 60)
+        },
+        None => name.to_owned(),
+    }
+}
+
+// One line per snake with its player's glyph, score, length and any powers it has.
+pub fn scores(frame: &Frame) -> Vec<String> {
+    frame.snakes.iter()
+        .map(|snake| {
+            let mut line = format!(
+                "{} Player {}  score {}  length {}",
+                HEADS[snake.player % HEADS.len()],
+                snake.player + 1,
+                snake.score,
+                snake.length
+            );
+            for &(power, remaining) in &snake.powers {
+                line.push_str(&format!("  {} {}", power.name(), remaining));
+            }
+            line
+        })
+        .collect()
+}
@@ -0,0 +1,168 @@
+
+// Colours the game is drawn with, loaded from the theme files in `resources/themes`.
+//
+// Every colour a theme uses is loaded as its own texture, named after the theme and what it
+// colours, so switching theme only changes which textures renderables are made with.
+
+use std::fs::{self, File};
+use std::io::{self, ErrorKind};
+use std::path::Path;
+
+use serde_yaml;
+
+pub type Colour = [f32; 4];
+
+// Colours for one player's snake.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Palette {
+    pub head: Colour,
+    pub body: Colour,
+}
+
+// Colours for each power-up, used for the item on the board and the snake that has it.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PowerColours {
+    pub ghost: Colour,
+    pub slow_time: Colour,
+    pub magnet: Colour,
+    pub shield: Colour,
+    pub reverse: Colour,
+}
+impl Default for PowerColours {
+    fn default() -> Self {
+        PowerColours {
+            ghost: [0.6, 0.6, 1.0, 1.0],
+            slow_time: [0.0, 0.8, 0.8, 1.0],
+            magnet: [1.0, 0.0, 1.0, 1.0],
+            shield: [1.0, 0.85, 0.0, 1.0],
+            reverse: [0.0, 1.0, 0.0, 1.0],
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Theme {
+    pub name: String,
+
+    // Cleared to behind everything, including the letterboxing around the board.
+    pub background: Colour,
+    pub board: Colour,
+    pub grid_lines: Colour,
+
+    // One palette per player, players past the end of the list start again from the first.
+    pub snakes: Vec<Palette>,
+    pub food: Colour,
+    pub walls: Colour,
+    pub hud: Colour,
+
+    pub poison: Colour,
+    pub portal: Colour,
+    pub gate: Colour,
+    pub ice: Colour,
+    pub conveyor: Colour,
+
+    pub power_ups: PowerColours,
+}
+impl Default for Theme {
+    fn default() -> Self {
+        Theme {
+            name: "classic".to_owned(),
+            background: [0.0, 0.0, 0.0, 1.0],
+            board: [0.1, 0.1, 0.1, 1.0],
+            grid_lines: [0.0, 0.0, 0.0, 1.0],
+            snakes: vec![
+                Palette {
+                    head: [1.0, 1.0, 1.0, 1.0],
+                    body: [0.75, 0.75, 0.75, 1.0],
+                },
+                Palette {
+                    head: [0.4, 0.6, 1.0, 1.0],
+                    body: [0.0, 0.0, 1.0, 1.0],
+                },
+            ],
+            food: [1.0, 0.0, 0.0, 1.0],
+            walls: [0.5, 0.5, 0.5, 1.0],
+            hud: [1.0, 1.0, 1.0, 1.0],
+            poison: [0.5, 0.0, 0.8, 1.0],
+            portal: [0.0, 0.6, 1.0, 1.0],
+            gate: [0.8, 0.5, 0.2, 1.0],
+            ice: [0.75, 0.9, 1.0, 1.0],
+            conveyor: [0.35, 0.35, 0.2, 1.0],
+            power_ups: PowerColours::default(),
+        }
+    }
+}
+impl Theme {
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Theme> {
+        let file = File::open(path)?;
+        serde_yaml::from_reader(file).map_err(|error| io::Error::new(ErrorKind::InvalidData, error))
+    }
+
+    // Every theme in a directory, in order of their file names. Files that fail to load are
+    // skipped, and the classic theme is used if there are none at all.
+    pub fn load_all<P: AsRef<Path>>(directory: P) -> Vec<Theme> {
+        let mut paths = fs::read_dir(directory)
+            .map(|entries| entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.extension().map(|extension| extension == "yml").unwrap_or(false))
+                .collect::<Vec<_>>())
+            .unwrap_or_default();
+        paths.sort();
+
+        let mut themes = Vec::new();
+        for path in paths {
+            match Theme::load(&path) {
+                Ok(theme) => themes.push(theme),
+                Err(error) => warn!("Skipping theme {}: {}", path.display(), error),
+            }
+        }
+
+        if themes.is_empty() {
+            themes.push(Theme::default());
+        }
+        themes
+    }
+
+    // Names of the textures for each part of the game.
+    pub fn texture(&self, part: &str) -> String {
+        format!("{}/{}", self.name, part)
+    }
+
+    pub fn head(&self, player: usize) -> String {
+        self.texture(&format!("head{}", player % self.snakes.len().max(1)))
+    }
+
+    pub fn body(&self, player: usize) -> String {
+        self.texture(&format!("body{}", player % self.snakes.len().max(1)))
+    }
+
+    // Every texture this theme needs, with its colour.
+    pub fn textures(&self) -> Vec<(String, Colour)> {
+        let mut textures = vec![
+            (self.texture("board"), self.board),
+            (self.texture("grid_lines"), self.grid_lines),
+            (self.texture("food"), self.food),
+            (self.texture("walls"), self.walls),
+            (self.texture("hud"), self.hud),
+            (self.texture("poison"), self.poison),
+            (self.texture("portal"), self.portal),
+            (self.texture("gate"), self.gate),
+            (self.texture("ice"), self.ice),
+            (self.texture("conveyor"), self.conveyor),
+            (self.texture("ghost"), self.power_ups.ghost),
+            (self.texture("slow_time"), self.power_ups.slow_time),
+            (self.texture("magnet"), self.power_ups.magnet),
+            (self.texture("shield"), self.power_ups.shield),
+            (self.texture("reverse"), self.power_ups.reverse),
+        ];
+
+        // A theme without any palettes still needs something to draw snakes with.
+        let default = Theme::default();
+        let snakes = if self.snakes.is_empty() { &default.snakes[..1] } else { &self.snakes[..] };
+        for (player, palette) in snakes.iter().enumerate() {
+            textures.push((self.head(player), palette.head));
+            textures.push((self.body(player), palette.body));
+        }
+        textures
+    }
+}
@@ -1,19 +1,56 @@
 
+use std::time::Duration;
+
 use amethyst::ecs::resources::Time;
 use specs::{Entities, Fetch, FetchMut, System};
 
 use components::Tick;
+use event::Events;
 
 pub const TICK_RATE: u64 = 133_333_333 ; // 1/7.5 of a second
 pub const TICKS_PER_SECOND: u64 = 1_000_000_000 / TICK_RATE;
 pub const FPS_SAMPLE: usize = 20;
 
+// How often the frame rate shown on the HUD is updated, so it can be read rather than flickering.
+pub const FPS_REFRESH: u64 = 250_000_000;
+
+// How many times longer a tick takes while time is slowed down.
+pub const SLOW_FACTOR: u64 = 2;
+
+// How long the current tick lasts in nanoseconds.
+pub fn rate(tick: &Tick) -> u64 {
+    if tick.slowed { TICK_RATE * SLOW_FACTOR } else { TICK_RATE }
+}
+
+// Start the next tick, which the rules run once unless the game is over. Every tick starts here, so
+// the event bus knows which events belong to the tick before and can let older ones go.
+pub fn next(tick: &mut Tick, events: &mut Events) {
+    tick.ticks += 1;
+    tick.ticked = !tick.game_over;
+    events.next_tick();
+}
+
+// How fast the game is running and how long it has been played, for the HUD.
+#[derive(Clone, Debug, Default)]
+pub struct Clock {
+    pub fps: f32,
+    pub shown_fps: f32,
+    pub ticks_per_second: f32,
+    pub elapsed: Duration,
+
+    // How far the clock is towards the next tick, from 0 to 1.
+    pub progress: f32,
+}
+
 // Deals with game ticks
 pub struct TickSystem {
     accumulator: u64,
 
     current: usize,
     average_fps: Vec<u64>,
+
+    // Nanoseconds since the frame rate shown was last updated.
+    since_shown: u64,
 }
 impl Default for TickSystem {
     fn default() -> Self {
@@ -26,6 +63,29 @@ impl Default for TickSystem {
             accumulator: 0,
             current: 0,
             average_fps: samples,
+            since_shown: FPS_REFRESH,
+        }
+    }
+}
+impl TickSystem {
+    // Advance the tick clock by how long the last frame took, returning whether a tick elapsed.
+    pub fn advance(&mut self, delta: Duration, tick: &mut Tick, events: &mut Events) -> bool {
+        let rate = rate(tick);
+        if delta.subsec_nanos() > rate as u32 {
+            self.accumulator = rate;
+        }
+        else {
+            self.accumulator += delta.subsec_nanos() as u64;
+        }
+
+        if self.accumulator >= rate {
+            self.accumulator = 0;
+            next(tick, events);
+            true
+        }
+        else {
+            tick.ticked = false;
+            false
         }
     }
 }
@@ -34,35 +94,31 @@ impl<'a> System<'a> for TickSystem {
         Entities<'a>,
         Fetch<'a, Time>,
         FetchMut<'a, Tick>,
+        FetchMut<'a, Clock>,
+        FetchMut<'a, Events>,
     );
-    fn run(&mut self, (_, time, mut tick): Self::SystemData) {
-        if time.delta_time.subsec_nanos() > TICK_RATE as u32 {
-            self.accumulator = TICK_RATE;
-        }
-        else {
-            self.accumulator += time.delta_time.subsec_nanos() as u64;
-        }
-        
+    fn run(&mut self, (_, time, mut tick, mut clock, mut events): Self::SystemData) {
         self.average_fps[self.current] = time.delta_time.subsec_nanos() as u64 / 1_000u64;
         self.current += 1;
         if self.current >= FPS_SAMPLE {
             self.current = 0;
         }
 
-        if self.accumulator >= TICK_RATE {
-            self.accumulator = 0;
-            tick.ticks += 1;
-            if !tick.game_over {
-                tick.ticked = true;
-            }
-
-            if tick.ticks % (TICKS_PER_SECOND * 3) == 0 { // print out every 3 seconds
-                let samples = self.average_fps.iter().sum::<u64>() / FPS_SAMPLE as u64;
-                println!("FPS: {:.2}", 1_000_000f64 / samples as f64);
-            }
+        let samples = self.average_fps.iter().sum::<u64>() / FPS_SAMPLE as u64;
+        if samples > 0 {
+            clock.fps = 1_000_000f32 / samples as f32;
         }
-        else {
-            tick.ticked = false;
+        self.since_shown += time.delta_time.subsec_nanos() as u64;
+        if self.since_shown >= FPS_REFRESH {
+            self.since_shown = 0;
+            clock.shown_fps = clock.fps;
+        }
+        clock.ticks_per_second = 1_000_000_000f32 / rate(&tick) as f32;
+        if !tick.game_over {
+            clock.elapsed += time.delta_time;
         }
+
+        self.advance(time.delta_time, &mut tick, &mut events);
+        clock.progress = self.accumulator as f32 / rate(&tick) as f32;
     }
 }
@@ -1,43 +1,139 @@
 
 use amethyst::ecs::components::LocalTransform;
 use amethyst::ecs::resources::{Camera, Projection, ScreenDimensions};
-use specs::{Entities, Fetch, FetchMut, Join, System, WriteStorage};
+use specs::{Entities, Fetch, FetchMut, Join, ReadStorage, System, WriteStorage};
 
-use components::{Tile, Tick};
-use ::TILE_SIZE;
+use components::{Board, GridLine, Previous, Tick, Tile};
+use grid::Grid;
+use tick::Clock;
 
-pub struct TileSystem(pub f32);
+// Fraction of a tile left empty between neighbouring tiles.
+pub const GAP: f32 = 0.03;
+
+// Where the board sits in the window, the largest square tiles that fit the whole grid with the
+// board centred and empty bars along the sides that are left over.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Layout {
+    pub tile: f32,
+    pub offset: (f32, f32),
+    pub size: (f32, f32),
+}
+impl Layout {
+    pub fn fit(screen: (f32, f32), grid: (usize, usize)) -> Layout {
+        let (width, height) = (grid.0.max(1) as f32, grid.1.max(1) as f32);
+        let tile = (screen.0 / width).min(screen.1 / height).max(0.0);
+        let size = (tile * width, tile * height);
+
+        Layout {
+            tile: tile,
+            offset: ((screen.0 - size.0) / 2.0, (screen.1 - size.1) / 2.0),
+            size: size,
+        }
+    }
+
+    // Centre of a tile on screen.
+    pub fn position(&self, x: f32, y: f32) -> (f32, f32) {
+        (self.offset.0 + (x + 0.5) * self.tile, self.offset.1 + (y + 0.5) * self.tile)
+    }
+
+    // Centre of the whole board on screen.
+    pub fn centre(&self) -> (f32, f32) {
+        (self.offset.0 + self.size.0 / 2.0, self.offset.1 + self.size.1 / 2.0)
+    }
+}
+
+// Fits the board to the window and puts everything on the grid where it belongs.
+#[derive(Default)]
+pub struct TileSystem {
+    // Slide things between tiles over the course of a tick instead of jumping at the end of it.
+    pub smooth: bool,
+}
 impl<'a> System<'a> for TileSystem {
     type SystemData = (
         // Resources
         Entities<'a>,
         Fetch<'a, ScreenDimensions>,
+        Fetch<'a, Grid>,
         Fetch<'a, Tick>,
+        Fetch<'a, Clock>,
         FetchMut<'a, Camera>,
+        FetchMut<'a, Layout>,
 
         // Components
-        WriteStorage<'a, Tile>,
+        ReadStorage<'a, Tile>,
+        ReadStorage<'a, Previous>,
+        ReadStorage<'a, Board>,
+        ReadStorage<'a, GridLine>,
         WriteStorage<'a, LocalTransform>,
     );
     fn run(&mut self, data: Self::SystemData) {
-        let (_, dimensions, tick, camera, mut tiles, mut locals) = data;
-
-        if tick.ticked {
-            // Update camera aspect ratio
-            match camera.proj {
-                Projection::Orthographic { left, mut right, mut bottom, top, near, far, }=> {
-                    right = dimensions.w;
-                    bottom = dimensions.h;
+        let (
+            entities,
+            dimensions,
+            grid,
+            tick,
+            clock,
+            mut camera,
+            mut layout,
+            tiles,
+            previous,
+            boards,
+            lines,
+            mut locals,
+        ) = data;
+
+        // Keep the camera showing exactly the window, one unit to a pixel.
+        camera.proj = Projection::Orthographic {
+            left: 0.0,
+            right: dimensions.w,
+            bottom: dimensions.h,
+            top: 0.0,
+            near: -1.0,
+            far: 1.0,
+        };
+        *layout = Layout::fit((dimensions.w, dimensions.h), grid.len);
+
+        // Nothing moves once the game is over, so stay put on the last tile.
+        let behind = if self.smooth && !tick.game_over { 1.0 - clock.progress } else { 0.0 };
+
+        for (entity, tile, locals) in (&*entities, &tiles, &mut locals).join() {
+            // Trail back towards the tile the last tick moved it from, until the next tick. Jumps
+            // through portals, conveyors carrying snakes aside and tails swapped with heads aren't a
+            // step to the next tile, those land straight away.
+            let moved = match previous.get(entity) {
+                Some(&Previous(ref from)) if (tile.x - from.x).abs() + (tile.y - from.y).abs() == 1 => {
+                    (tile.x - from.x, tile.y - from.y)
                 },
-                _ => { },
-            }
+                _ => (0, 0),
+            };
+            let (x, y) = layout.position(
+                tile.x as f32 - moved.0 as f32 * behind,
+                tile.y as f32 - moved.1 as f32 * behind,
+            );
+            locals.translation[0] = x;
+            locals.translation[1] = y;
+            locals.scale[0] = layout.tile * (1.0 - GAP);
+            locals.scale[1] = layout.tile * (1.0 - GAP);
+        }
 
-            // Fix perspective for proper scaling
-            for (tile, locals) in (&mut tiles, &mut locals).join() {
-                locals.translation[0] = tile.x as f32 * (TILE_SIZE / 2.0) + (TILE_SIZE / 4.0);
-                locals.translation[1] = tile.y as f32 * (TILE_SIZE / 2.0) + (TILE_SIZE / 4.0);
-                locals.scale[0] = TILE_SIZE / 2.0 - 1.5;
-                locals.scale[1] = TILE_SIZE / 2.0 - 1.5;
+        for (_, locals) in (&boards, &mut locals).join() {
+            let (x, y) = layout.centre();
+            locals.translation = [x, y, -0.5];
+            locals.scale = [layout.size.0, layout.size.1, 1.0];
+        }
+
+        // Grid lines run along the gaps between tiles, in front of the backdrop.
+        let thickness = (layout.tile * GAP).max(1.0);
+        for (line, locals) in (&lines, &mut locals).join() {
+            let (x, y) = layout.centre();
+            let along = layout.tile * line.index as f32;
+            if line.vertical {
+                locals.translation = [layout.offset.0 + along, y, -0.4];
+                locals.scale = [thickness, layout.size.1, 1.0];
+            }
+            else {
+                locals.translation = [x, layout.offset.1 + along, -0.4];
+                locals.scale = [layout.size.0, thickness, 1.0];
             }
         }
     }
@@ -0,0 +1,179 @@
+
+// A world with the rules for moving, eating, shrinking, dying and growing, run a tick at a time with
+// scripted input in place of the keyboard.
+
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+
+use specs::{Dispatcher, DispatcherBuilder, Entity, Join, World};
+
+use snake::components::{Direction, Parent, Player, Snake, Tick, Tile, Type};
+use snake::event::{Cause, Events, GameEvent, Reader};
+use snake::grid::Grid;
+use snake::{simulation, tick};
+
+pub const SEED: u64 = 1;
+
+// A player steering a way before a tick, counting the first tick as 1.
+#[derive(Clone, Copy, Debug)]
+pub struct Input {
+    pub tick: u64,
+    pub player: usize,
+    pub direction: (i8, i8),
+}
+
+// Inputs in place of the keyboard, fed to the snakes as the ticks they're for come up.
+#[derive(Clone, Debug, Default)]
+pub struct Script(pub Vec<Input>);
+impl Script {
+    pub fn new() -> Script {
+        Script::default()
+    }
+
+    pub fn steer(mut self, tick: u64, player: usize, direction: (i8, i8)) -> Script {
+        self.0.push(Input { tick: tick, player: player, direction: direction });
+        self
+    }
+
+    fn inputs(&self, tick: u64) -> Vec<Input> {
+        self.0.iter().filter(|input| input.tick == tick).cloned().collect()
+    }
+}
+
+pub struct Harness {
+    pub world: World,
+    dispatcher: Dispatcher<'static, 'static>,
+    reader: Reader,
+}
+impl Harness {
+    pub fn new(x: usize, y: usize) -> Harness {
+        let mut world = World::new();
+        simulation::register(&mut world, x, y, SEED);
+
+        let dispatcher = simulation::movement(DispatcherBuilder::new(), &[]).build();
+        let reader = world.read_resource::<Events>().reader();
+
+        Harness {
+            world: world,
+            dispatcher: dispatcher,
+            reader: reader,
+        }
+    }
+
+    // A new snake heading up the board, the same as at the start of a game.
+    pub fn snake(&mut self, player: usize, x: i32, y: i32) -> Entity {
+        simulation::spawn_snake(&mut self.world, player, Tile { x: x, y: y })
+    }
+
+    pub fn wall(&mut self, x: usize, y: usize) -> Entity {
+        self.place(x, y, Type::Wall)
+    }
+
+    pub fn food(&mut self, x: usize, y: usize) -> Entity {
+        self.place(x, y, Type::Food)
+    }
+
+    fn place(&mut self, x: usize, y: usize, kind: Type) -> Entity {
+        let entity = self.world.create_entity()
+            .with(Tile { x: x as i32, y: y as i32 })
+            .with(kind)
+            .build();
+        self.world.write_resource::<Grid>().insert(x, y, entity, kind);
+        entity
+    }
+
+    // Queue a direction for a player's snake, the way the keyboard does.
+    pub fn steer(&mut self, player: usize, wanted: (i8, i8)) {
+        let directions = self.world.read::<Direction>();
+        let players = self.world.read::<Player>();
+        let mut snakes = self.world.write::<Snake>();
+        for (snake, direction, owner) in (&mut snakes, &directions, &players).join() {
+            if owner.0 == player {
+                snake.steer(direction, wanted);
+            }
+        }
+    }
+
+    // Run the rules for one tick.
+    pub fn tick(&mut self) {
+        tick::next(&mut self.world.write_resource::<Tick>(), &mut self.world.write_resource::<Events>());
+        self.dispatcher.dispatch(&mut self.world.res);
+        self.world.maintain();
+    }
+
+    // Run a number of ticks, steering the snakes as the script says.
+    pub fn play(&mut self, script: &Script, ticks: u64) {
+        for _ in 0..ticks {
+            let next = self.ticks() + 1;
+            for input in script.inputs(next) {
+                self.steer(input.player, input.direction);
+            }
+            self.tick();
+        }
+    }
+
+    pub fn ticks(&self) -> u64 {
+        self.world.read_resource::<Tick>().ticks
+    }
+
+    pub fn game_over(&self) -> bool {
+        self.world.read_resource::<Tick>().game_over
+    }
+
+    pub fn alive(&self, entity: Entity) -> bool {
+        self.world.entities().is_alive(entity)
+    }
+
+    pub fn tile(&self, entity: Entity) -> Option<(i32, i32)> {
+        self.world.read::<Tile>().get(entity).map(|tile| (tile.x, tile.y))
+    }
+
+    pub fn length(&self, head: Entity) -> u32 {
+        self.world.read::<Snake>().get(head).map(|snake| snake.length).expect("not a snake")
+    }
+
+    pub fn score(&self, head: Entity) -> u32 {
+        self.world.read::<Snake>().get(head).map(|snake| snake.score).expect("not a snake")
+    }
+
+    // Positions of each segment of a snake, starting at the head.
+    pub fn body(&self, head: Entity) -> Vec<(i32, i32)> {
+        self.segments(head).into_iter().filter_map(|segment| self.tile(segment)).collect()
+    }
+
+    // Each segment of a snake, following the parents back from the head.
+    pub fn segments(&self, head: Entity) -> Vec<Entity> {
+        let entities = self.world.entities();
+        let parents = self.world.read::<Parent>();
+        let followers = (&*entities, &parents).join()
+            .map(|(entity, parent)| (parent.0, entity))
+            .collect::<HashMap<_, _>>();
+
+        let mut segments = vec![head];
+        while let Some(&follower) = followers.get(segments.last().unwrap()) {
+            segments.push(follower);
+        }
+        segments
+    }
+
+    // What the grid has on top at a position.
+    pub fn at(&self, x: usize, y: usize) -> Option<Type> {
+        self.world.read_resource::<Grid>().occupant_kind(x, y)
+    }
+
+    // Every event published since the last call.
+    pub fn events(&mut self) -> Vec<GameEvent> {
+        self.world.read_resource::<Events>().read(&mut self.reader)
+    }
+
+    // How each snake died since the events were last read.
+    pub fn deaths(&mut self) -> Vec<(Entity, Cause)> {
+        self.events().into_iter()
+            .filter_map(|event| match event {
+                GameEvent::SnakeDied { who, cause, .. } => Some((who, cause)),
+                _ => None,
+            })
+            .collect()
+    }
+}
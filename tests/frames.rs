@@ -0,0 +1,114 @@
+
+// Pictures of the screen after playing a few seeded games, drawn as text and compared against the
+// ones kept in `tests/frames`. When the game is meant to look different, run with
+// `SNAKE_FRAMES_UPDATE=1` to write them out again and check the difference looks right.
+
+extern crate snake;
+
+use std::env;
+use std::fs::File;
+use std::io::{Read, Write};
+
+use snake::level::Level;
+use snake::raster;
+use snake::simulation::Simulation;
+use snake::theme::Theme;
+
+const UP: (i8, i8) = (0, -1);
+const DOWN: (i8, i8) = (0, 1);
+const LEFT: (i8, i8) = (-1, 0);
+const RIGHT: (i8, i8) = (1, 0);
+
+const SEED: u64 = 1;
+
+// How many characters the screen is drawn across and down, about two across for each one down to
+// keep tiles square.
+const COLUMNS: usize = 96;
+const ROWS: usize = 48;
+
+fn theme(name: &str) -> Theme {
+    Theme::load_all(format!("{}/resources/themes", env!("CARGO_MANIFEST_DIR")))
+        .into_iter()
+        .find(|theme| theme.name == name)
+        .expect("no such theme")
+}
+
+// Play a game for a number of ticks, steering as the inputs say before each tick, then draw the
+// screen as the window would show it.
+fn play(
+    simulation: &mut Simulation,
+    ticks: u64,
+    inputs: &[(u64, usize, (i8, i8))],
+    screen: (f32, f32),
+    theme: &Theme,
+) -> String {
+    for tick in 1..ticks + 1 {
+        let steering = inputs.iter()
+            .filter(|&&(at, _, _)| at == tick)
+            .map(|&(_, player, direction)| (player, direction))
+            .collect::<Vec<_>>();
+        simulation.step_with(&steering);
+    }
+    assert!(!simulation.game_over(), "a snake died before the picture was taken");
+
+    raster::offscreen(&mut simulation.world, screen.0, screen.1);
+    raster::update(&mut simulation.world, theme);
+    raster::draw(&simulation.world, theme, COLUMNS, ROWS).to_string()
+}
+
+// Compare a picture against the one kept under a name.
+fn check(name: &str, picture: String) {
+    let path = format!("{}/tests/frames/{}.txt", env!("CARGO_MANIFEST_DIR"), name);
+    if env::var("SNAKE_FRAMES_UPDATE").is_ok() {
+        File::create(&path)
+            .and_then(|mut file| file.write_all(picture.as_bytes()))
+            .expect("failed to write the frame");
+    }
+
+    let mut expected = String::new();
+    File::open(&path)
+        .and_then(|mut file| file.read_to_string(&mut expected))
+        .expect("no frame kept, run with SNAKE_FRAMES_UPDATE=1 to make one");
+    assert!(picture == expected, "{} looks different:\n{}\nexpected:\n{}", name, picture, expected);
+}
+
+// A snake going round in a square on a board that fills the window, with the first food out.
+#[test]
+fn classic() {
+    let mut simulation = Simulation::seeded(12, 12, 1, SEED);
+    let inputs = [
+        (5, 0, RIGHT), (11, 0, DOWN), (17, 0, LEFT), (23, 0, UP),
+        (29, 0, RIGHT), (35, 0, DOWN), (41, 0, LEFT), (47, 0, UP),
+        (53, 0, RIGHT),
+    ];
+    let picture = play(&mut simulation, 55, &inputs, (480.0, 480.0), &theme("classic"));
+    check("classic", picture);
+}
+
+// Two snakes on a window wider than the board, which is centred with bars either side of it.
+#[test]
+fn letterboxed() {
+    let mut simulation = Simulation::seeded(12, 12, 2, SEED);
+    let inputs = [(4, 0, LEFT), (6, 0, UP), (6, 1, LEFT), (7, 1, UP)];
+    let picture = play(&mut simulation, 9, &inputs, (720.0, 360.0), &theme("high-contrast"));
+    check("letterboxed", picture);
+}
+
+// Walls and portals from a level, drawn in another theme's colours.
+#[test]
+fn level() {
+    let mut simulation = Simulation::seeded(12, 12, 1, SEED);
+    simulation.level(Level::named("portals").unwrap());
+    let inputs = [(3, 0, LEFT), (4, 0, DOWN)];
+    let picture = play(&mut simulation, 6, &inputs, (480.0, 480.0), &theme("colour-blind"));
+    check("level", picture);
+}
+
+// Gates and conveyors drawn as arrows and chevrons pointing the way they go, next to ice.
+#[test]
+fn terrain() {
+    let mut simulation = Simulation::seeded(12, 12, 1, SEED);
+    simulation.level(Level::named("rink").unwrap());
+    let picture = play(&mut simulation, 3, &[], (480.0, 480.0), &theme("classic"));
+    check("terrain", picture);
+}
@@ -0,0 +1,309 @@
+
+// Plays games with random players, modes, levels and inputs, checking after every tick that the
+// board still makes sense. Each game is played from a seed, so a failure can be played again with
+// `SNAKE_FUZZ_SEED=<seed>`. `SNAKE_FUZZ_RUNS` sets how many games are played.
+
+extern crate rand;
+extern crate snake;
+extern crate specs;
+
+use std::collections::{HashMap, HashSet};
+use std::env;
+
+use rand::Rng;
+use specs::{Entity, Join, World};
+
+use snake::components::{Direction, Parent, Player, Power, Powers, Snake, Tick, Tile, Type};
+use snake::event::{Events, GameEvent, Reader};
+use snake::grid::{Grid, Layer};
+use snake::level::Level;
+use snake::mode::MODES;
+use snake::random::Random;
+use snake::simulation::Simulation;
+
+const DIRECTIONS: [(i8, i8); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+const LAYERS: [Layer; 3] = [Layer::Terrain, Layer::Item, Layer::Actor];
+
+// Levels to play on, where an empty name is an empty board.
+const LEVELS: [&'static str; 4] = ["", "pillars", "portals", "rink"];
+
+// How long each game goes on for if nobody wins before then.
+const TICKS: u64 = 600;
+
+fn setting(name: &str) -> Option<u64> {
+    env::var(name).ok().and_then(|value| value.parse().ok())
+}
+
+#[test]
+fn invariants_hold_every_tick() {
+    match setting("SNAKE_FUZZ_SEED") {
+        Some(seed) => play(seed),
+        None => for seed in 0..setting("SNAKE_FUZZ_RUNS").unwrap_or(200) {
+            play(seed);
+        },
+    }
+}
+
+// Set up a game from a seed and play it out with random inputs.
+fn play(seed: u64) {
+    let mut random = Random::new(seed);
+    let players = random.gen_range(1, 5);
+    let (x, y) = (random.gen_range(8, 25), random.gen_range(8, 25));
+    let mode = *random.choose(&MODES).unwrap();
+    let level = *random.choose(&LEVELS).unwrap();
+
+    let mut simulation = Simulation::seeded(x, y, players, seed);
+    if !level.is_empty() {
+        simulation.level(Level::named(level).unwrap());
+    }
+    simulation.mode(mode);
+    let mut reader = simulation.world.read_resource::<Events>().reader();
+
+    // Ghosts and shields let snakes through each other, and the bodies then cross over on those
+    // tiles until they've all moved off.
+    let mut crossings = Crossings::new();
+
+    let fail = |simulation: &Simulation, error: String| -> ! {
+        panic!(
+            "seed {} ({} players on {}x{} {:?} playing {}) tick {}: {}",
+            seed, players, x, y, level, mode.name(), simulation.ticks(), error,
+        )
+    };
+    while simulation.ticks() < TICKS && !simulation.game_over() {
+        let inputs = steer(&simulation.world, &mut random);
+        simulation.step_with(&inputs);
+
+        passed_through(&simulation.world, &mut reader, &mut crossings);
+        if let Err(error) = check(&simulation.world, &crossings) {
+            fail(&simulation, error);
+        }
+    }
+}
+
+// Players mostly steer clear of whatever is in front of them, so games go on long enough for the
+// snakes to grow, eat and get caught by the arena, but every so often turn any way at all.
+fn steer(world: &World, random: &mut Random) -> Vec<(usize, (i8, i8))> {
+    let grid = world.read_resource::<Grid>();
+    let snakes = world.read::<Snake>();
+    let players = world.read::<Player>();
+    let tiles = world.read::<Tile>();
+    let directions = world.read::<Direction>();
+
+    let mut inputs = Vec::new();
+    for (_, player, tile, direction) in (&snakes, &players, &tiles, &directions).join() {
+        if random.gen_weighted_bool(20) {
+            inputs.push((player.0, *random.choose(&DIRECTIONS).unwrap()));
+            continue;
+        }
+
+        let safe = DIRECTIONS.iter()
+            .cloned()
+            .filter(|&way| way != (-direction.direction.0, -direction.direction.1))
+            .filter(|&way| {
+                let (x, y) = (tile.x + way.0 as i32, tile.y + way.1 as i32);
+                x >= 0 && y >= 0 && grid.bounds(x as usize, y as usize) && grid.occupant_kind(x as usize, y as usize)
+                    .map(|kind| !kind.blocks(way))
+                    .unwrap_or(true)
+            })
+            .collect::<Vec<_>>();
+        if safe.contains(&direction.direction) && !random.gen_weighted_bool(4) {
+            continue;
+        }
+        if let Some(&way) = random.choose(&safe) {
+            inputs.push((player.0, way));
+        }
+    }
+    inputs
+}
+
+// Tiles snakes got through each other on with a power, with the heads of the snakes crossing there.
+type Crossings = HashMap<(i32, i32), HashSet<Entity>>;
+
+// Note the tiles heads got through other snakes on this tick, with a shield or as a ghost going
+// through its own body, and forget the ones every snake has since moved off.
+fn passed_through(world: &World, reader: &mut Reader, crossings: &mut Crossings) {
+    let events = world.read_resource::<Events>().read(reader);
+    let entities = world.entities();
+    let tiles = world.read::<Tile>();
+    let types = world.read::<Type>();
+    let powers = world.read::<Powers>();
+    let owners = owners(world);
+
+    // Ghosts that ran out this tick still went through as ghosts.
+    let expired = events.iter()
+        .filter_map(|event| match *event {
+            GameEvent::PowerExpired { snake, power: Power::Ghost } => Some(snake),
+            _ => None,
+        })
+        .collect::<HashSet<Entity>>();
+    let ghost = |snake| {
+        expired.contains(&snake) || powers.get(snake).map(|powers| powers.has(Power::Ghost)).unwrap_or(false)
+    };
+
+    let snakes_on = |x, y| (&*entities, &tiles, &types).join()
+        .filter(|&(_, tile, &kind)| kind == Type::Snake && (tile.x, tile.y) == (x, y))
+        .map(|(entity, _, _)| entity)
+        .collect::<Vec<_>>();
+
+    for event in &events {
+        let tile = match *event {
+            GameEvent::ShieldBroken { ref tile, .. } => tile,
+            GameEvent::SnakeMoved { snake, ref to, .. } if ghost(snake) => to,
+            _ => continue,
+        };
+
+        // Shields also get heads through walls, which they don't share a layer with.
+        let sharing = snakes_on(tile.x, tile.y);
+        if sharing.len() < 2 {
+            continue;
+        }
+        let heads = crossings.entry((tile.x, tile.y)).or_default();
+        heads.extend(sharing.iter().filter_map(|segment| owners.get(segment)));
+    }
+
+    crossings.retain(|&(x, y), _| !snakes_on(x, y).is_empty());
+}
+
+// The head of the snake each segment belongs to, heads included.
+fn owners(world: &World) -> HashMap<Entity, Entity> {
+    let entities = world.entities();
+    let snakes = world.read::<Snake>();
+    let parents = world.read::<Parent>();
+
+    let followers = (&*entities, &parents).join()
+        .map(|(entity, parent)| (parent.0, entity))
+        .collect::<HashMap<Entity, Entity>>();
+    let mut owners = HashMap::new();
+    for (head, _) in (&*entities, &snakes).join() {
+        let mut segment = head;
+        while owners.insert(segment, head).is_none() {
+            match followers.get(&segment) {
+                Some(&follower) => segment = follower,
+                None => break,
+            }
+        }
+    }
+    owners
+}
+
+// Whether the board, the tiles and the snakes all agree with each other.
+fn check(world: &World, crossings: &Crossings) -> Result<(), String> {
+    let tick = world.read_resource::<Tick>();
+    let grid = world.read_resource::<Grid>();
+    let entities = world.entities();
+    let tiles = world.read::<Tile>();
+    let types = world.read::<Type>();
+    let snakes = world.read::<Snake>();
+    let parents = world.read::<Parent>();
+    let owners = owners(world);
+
+    // Everything on the grid is still around, and is where its tile says it is.
+    for x in 0..grid.len.0 {
+        for y in 0..grid.len.1 {
+            for &layer in &LAYERS {
+                let occupant = match grid.get(layer, x, y) {
+                    Some(occupant) => occupant,
+                    None => continue,
+                };
+
+                if !entities.is_alive(occupant.entity) {
+                    return Err(format!("{:?} at {:?} is gone", occupant, (x, y)));
+                }
+                let tile = tiles.get(occupant.entity).map(|tile| (tile.x, tile.y));
+                if tile != Some((x as i32, y as i32)) {
+                    return Err(format!("{:?} at {:?} has the tile {:?}", occupant, (x, y), tile));
+                }
+                if types.get(occupant.entity) != Some(&occupant.kind) {
+                    return Err(format!("{:?} at {:?} is a {:?}", occupant, (x, y), types.get(occupant.entity)));
+                }
+            }
+        }
+    }
+
+    // Everything with a tile is on the grid there, so nothing shares a cell. Growth that hasn't
+    // moved out from under the end of the tail yet is the exception, along with the segments of
+    // snakes crossing where one got through the other with a power, under another of them or
+    // where the other may since have died and left the cell empty, and heads that died ending the
+    // game.
+    for (entity, tile, kind) in (&*entities, &tiles, &types).join() {
+        let (x, y) = (tile.x as usize, tile.y as usize);
+        let occupant = if tile.x < 0 || tile.y < 0 { None } else { grid.get(kind.layer(), x, y) };
+        if occupant.map(|occupant| occupant.entity) == Some(entity) {
+            continue;
+        }
+
+        let growth = parents.get(entity).and_then(|parent| tiles.get(parent.0)) == Some(tile);
+        let crossing = *kind == Type::Snake && crossings.get(&(tile.x, tile.y))
+            .map(|heads| {
+                let crossing = |segment| owners.get(&segment).map(|head| heads.contains(head)).unwrap_or(false);
+                crossing(entity) && occupant.map(|occupant| crossing(occupant.entity)).unwrap_or(true)
+            })
+            .unwrap_or(false);
+        let dead = tick.game_over && snakes.get(entity).is_some();
+        if !(growth || crossing || dead) {
+            return Err(format!("{:?} {:?} at {:?} isn't on the grid, {:?} is", entity, kind, tile, occupant));
+        }
+    }
+
+    // Each snake is as long as the segments following it, and they trail along one after another.
+    let followers = (&*entities, &parents).join()
+        .map(|(entity, parent)| (parent.0, entity))
+        .collect::<HashMap<Entity, Entity>>();
+    for (head, snake) in (&*entities, &snakes).join() {
+        let mut body = vec![head];
+        while let Some(&follower) = followers.get(body.last().unwrap()) {
+            if body.len() > followers.len() {
+                return Err(format!("{:?} goes round in a loop", head));
+            }
+            body.push(follower);
+        }
+
+        if body.len() != snake.length as usize {
+            return Err(format!("{:?} is {} long with {} segments", head, snake.length, body.len()));
+        }
+        let end = if body.len() > 1 { body.last().cloned() } else { None };
+        if snake.end != end {
+            return Err(format!("{:?} ends at {:?} instead of {:?}", head, snake.end, end));
+        }
+
+        for pair in body.windows(2) {
+            let (parent, follower) = match (tiles.get(pair[0]), tiles.get(pair[1])) {
+                (Some(parent), Some(follower)) => (parent, follower),
+                _ => return Err(format!("{:?} has a segment without a tile", head)),
+            };
+            if !adjacent(parent, follower) && !through(&grid, follower, parent) && !through(&grid, parent, follower) {
+                return Err(format!("{:?} is split between {:?} and {:?}", head, parent, follower));
+            }
+        }
+    }
+
+    // Nothing follows a segment that has gone.
+    for (entity, parent) in (&*entities, &parents).join() {
+        if !entities.is_alive(parent.0) || tiles.get(parent.0).is_none() {
+            return Err(format!("{:?} follows {:?}, which is gone", entity, parent.0));
+        }
+    }
+
+    Ok(())
+}
+
+fn adjacent(a: &Tile, b: &Tile) -> bool {
+    (a.x - b.x).abs() + (a.y - b.y).abs() <= 1
+}
+
+// Whether a snake can get from one tile to another in a single move through a portal or over a
+// conveyor next to it.
+fn through(grid: &Grid, from: &Tile, to: &Tile) -> bool {
+    DIRECTIONS.iter().any(|&(x, y)| {
+        let (next_x, next_y) = (from.x + x as i32, from.y + y as i32);
+        if next_x < 0 || next_y < 0 {
+            return false;
+        }
+
+        match grid.get(Layer::Terrain, next_x as usize, next_y as usize).map(|occupant| occupant.kind) {
+            Some(Type::Portal { x, y }) => (x as i32, y as i32) == (to.x, to.y),
+            Some(Type::Conveyor(way)) => (next_x + way.0 as i32, next_y + way.1 as i32) == (to.x, to.y),
+            _ => false,
+        }
+    })
+}
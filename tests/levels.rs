@@ -0,0 +1,50 @@
+
+// Level files, the ones that come with the game and ones that put terrain where it can't go.
+
+extern crate snake;
+
+use std::fs;
+use std::io::ErrorKind;
+
+use snake::level::{self, Level};
+
+#[test]
+fn every_level_loads() {
+    let directory = format!("{}/{}", env!("CARGO_MANIFEST_DIR"), level::LEVEL_DIR);
+    for entry in fs::read_dir(directory).expect("no level directory") {
+        let path = entry.unwrap().path();
+        if let Err(error) = Level::load(&path) {
+            panic!("{} didn't load: {}", path.display(), error);
+        }
+    }
+}
+
+#[test]
+fn terrain_on_top_of_a_wall_is_turned_away() {
+    let text = "
+name: crowded
+walls: [[1, 1], [2, 1], [3, 1]]
+ice: [[5, 5]]
+conveyors: [[[2, 1], [1, 0]]]
+";
+    let error = Level::parse(text).expect_err("the conveyor went on top of the wall");
+    assert_eq!(error.kind(), ErrorKind::InvalidData);
+    assert_eq!(error.to_string(), "level \"crowded\" has a wall and a conveyor on tile (2, 1)");
+}
+
+#[test]
+fn both_ends_of_a_portal_need_tiles_of_their_own() {
+    let text = "
+name: loop
+portals: [[[4, 4], [4, 4]]]
+";
+    let error = Level::parse(text).expect_err("the portal went into itself");
+    assert!(error.to_string().contains("(4, 4)"), "{}", error);
+
+    let apart = "
+name: apart
+portals: [[[4, 4], [8, 8]]]
+gates: [[[6, 6], [0, -1]]]
+";
+    assert!(Level::parse(apart).is_ok());
+}
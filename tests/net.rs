@@ -0,0 +1,96 @@
+
+// Networked games played over 127.0.0.1, with the server and both clients stepped a tick at a time
+// from the test so every peer can be checked after each one.
+
+extern crate snake;
+
+use std::io::ErrorKind;
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+use std::time::Duration;
+
+use snake::net::{Client, Server};
+
+const SEED: u64 = 7;
+const GRID: (usize, usize) = (12, 12);
+
+const UP: (i8, i8) = (0, -1);
+const LEFT: (i8, i8) = (-1, 0);
+const RIGHT: (i8, i8) = (1, 0);
+
+// A server with two clients connected to it, the first playing player 0 and the second player 1.
+// The connections wait to be accepted until the server starts, so it all happens on one thread.
+fn connect() -> (Server, Client, Client) {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("failed to listen");
+    let address = listener.local_addr().unwrap();
+    let first = TcpStream::connect(address).expect("failed to connect the first client");
+    let second = TcpStream::connect(address).expect("failed to connect the second client");
+
+    let server = Server::accept(&listener, 2, GRID, SEED).expect("failed to start the server");
+    (server, Client::join(first).unwrap(), Client::join(second).unwrap())
+}
+
+// Give inputs sent by clients time to reach the server, then run a tick with them.
+fn tick(server: &mut Server) {
+    thread::sleep(Duration::from_millis(5));
+    server.receive().expect("failed to receive inputs");
+    server.tick().expect("failed to send the tick");
+}
+
+#[test]
+fn peers_stay_in_lockstep() {
+    let (mut server, mut first, mut second) = connect();
+    assert_eq!((first.player, second.player), (0, 1));
+
+    // Both snakes head up, then turn towards each other and back up again.
+    let script = [(3, 0, RIGHT), (3, 1, LEFT), (5, 0, UP), (5, 1, UP)];
+    for ticks in 1..10 {
+        for &(at, player, direction) in &script {
+            if at == ticks {
+                let client = if player == 0 { &mut first } else { &mut second };
+                client.steer(direction).unwrap();
+            }
+        }
+
+        tick(&mut server);
+        first.wait().expect("the first client fell out of sync");
+        second.wait().expect("the second client fell out of sync");
+
+        let checksum = server.simulation.checksum();
+        assert_eq!(first.simulation.checksum(), checksum);
+        assert_eq!(second.simulation.checksum(), checksum);
+        assert_eq!(first.simulation.ticks(), ticks);
+    }
+    assert!(!server.simulation.game_over());
+}
+
+#[test]
+fn diverging_from_the_server_is_reported() {
+    let (mut server, mut first, mut second) = connect();
+    for _ in 0..3 {
+        tick(&mut server);
+        first.wait().unwrap();
+        second.wait().unwrap();
+    }
+
+    // The second client turns its snake without telling the server.
+    second.simulation.steer(1, LEFT);
+    tick(&mut server);
+
+    first.wait().expect("the first client fell out of sync");
+    let error = second.wait().expect_err("the second client didn't notice it was out of sync");
+    assert_eq!(error.kind(), ErrorKind::InvalidData);
+}
+
+#[test]
+fn the_server_carries_on_when_a_client_leaves() {
+    let (mut server, first, mut second) = connect();
+    drop(first);
+
+    for _ in 0..3 {
+        tick(&mut server);
+        second.wait().expect("the second client stopped getting ticks");
+        assert_eq!(second.simulation.checksum(), server.simulation.checksum());
+    }
+    assert_eq!(server.connected(), 1);
+}
@@ -0,0 +1,344 @@
+
+// The game rules as they play out tick by tick: moving, growing, eating and dying.
+
+extern crate snake;
+extern crate specs;
+
+mod common;
+
+use specs::{Entity, Join};
+
+use snake::components::{Expiry, Extension, Snake, Tile, Type};
+use snake::event::{Cause, Events, GameEvent, CAPACITY};
+use snake::food::{SpawnRules, When};
+use snake::grid::Grid;
+use snake::outcome::GROWTH;
+use snake::simulation::Simulation;
+
+use common::{Harness, Script, SEED};
+
+const DOWN: (i8, i8) = (0, 1);
+const LEFT: (i8, i8) = (-1, 0);
+const RIGHT: (i8, i8) = (1, 0);
+
+#[test]
+fn snakes_start_heading_up() {
+    let mut harness = Harness::new(12, 12);
+    let head = harness.snake(0, 5, 9);
+
+    harness.tick();
+    assert_eq!(harness.tile(head), Some((5, 8)));
+    harness.tick();
+    assert_eq!(harness.tile(head), Some((5, 7)));
+}
+
+#[test]
+fn snakes_start_with_seven_pieces() {
+    let mut harness = Harness::new(12, 12);
+    let head = harness.snake(0, 5, 10);
+    assert_eq!(harness.length(head), 1);
+
+    // One piece is added every tick until the starting extension runs out.
+    for ticks in 1..7 {
+        harness.tick();
+        assert_eq!(harness.length(head), 1 + ticks);
+    }
+    assert!(harness.world.read::<Extension>().get(head).is_none());
+
+    // The newest piece moves out from under the end of the tail the tick after it's added.
+    harness.tick();
+    assert_eq!(harness.length(head), 7);
+    assert_eq!(harness.body(head), vec![(5, 3), (5, 4), (5, 5), (5, 6), (5, 7), (5, 8), (5, 9)]);
+    for y in 3..10 {
+        assert_eq!(harness.at(5, y), Some(Type::Snake));
+    }
+    assert_eq!(harness.at(5, 10), None);
+}
+
+#[test]
+fn tails_follow_the_head_around_corners() {
+    let mut harness = Harness::new(12, 12);
+    let head = harness.snake(0, 2, 10);
+
+    let script = Script::new()
+        .steer(8, 0, RIGHT)
+        .steer(11, 0, DOWN);
+    harness.play(&script, 12);
+
+    assert_eq!(harness.body(head), vec![(5, 5), (5, 4), (5, 3), (4, 3), (3, 3), (2, 3), (2, 4)]);
+    assert!(!harness.game_over());
+}
+
+#[test]
+fn snakes_cannot_turn_back_on_themselves() {
+    let mut harness = Harness::new(12, 12);
+    let head = harness.snake(0, 5, 9);
+
+    let script = Script::new()
+        .steer(2, 0, DOWN)
+        .steer(3, 0, LEFT)
+        .steer(4, 0, RIGHT);
+    harness.play(&script, 4);
+
+    assert_eq!(harness.tile(head), Some((3, 7)));
+    assert!(harness.deaths().is_empty());
+}
+
+#[test]
+fn only_the_last_direction_before_a_tick_counts() {
+    let mut harness = Harness::new(12, 12);
+    let head = harness.snake(0, 5, 9);
+
+    let script = Script::new()
+        .steer(2, 0, LEFT)
+        .steer(2, 0, RIGHT);
+    harness.play(&script, 2);
+
+    assert_eq!(harness.tile(head), Some((6, 8)));
+}
+
+#[test]
+fn running_off_the_board_kills() {
+    let mut harness = Harness::new(12, 12);
+    let head = harness.snake(0, 5, 2);
+
+    harness.play(&Script::new(), 2);
+    assert!(harness.deaths().is_empty());
+    assert!(!harness.game_over());
+
+    harness.tick();
+    assert_eq!(harness.deaths(), vec![(head, Cause::Edge)]);
+    assert!(harness.game_over());
+}
+
+#[test]
+fn running_into_a_wall_kills() {
+    let mut harness = Harness::new(12, 12);
+    let head = harness.snake(0, 5, 9);
+    harness.wall(5, 6);
+
+    harness.play(&Script::new(), 2);
+    assert!(harness.deaths().is_empty());
+
+    harness.tick();
+    assert_eq!(harness.deaths(), vec![(head, Cause::Hit(Type::Wall))]);
+    assert!(harness.game_over());
+
+    // The wall keeps its tile and the dead head stays off the grid.
+    assert_eq!(harness.at(5, 6), Some(Type::Wall));
+}
+
+#[test]
+fn running_into_itself_kills() {
+    let mut harness = Harness::new(12, 12);
+    let head = harness.snake(0, 5, 10);
+
+    let script = Script::new()
+        .steer(8, 0, RIGHT)
+        .steer(9, 0, DOWN)
+        .steer(10, 0, LEFT);
+    harness.play(&script, 9);
+    assert!(harness.deaths().is_empty());
+
+    harness.play(&script, 1);
+    assert_eq!(harness.deaths(), vec![(head, Cause::Hit(Type::Snake))]);
+    assert!(harness.game_over());
+}
+
+#[test]
+fn chasing_the_end_of_the_tail_kills() {
+    let mut harness = Harness::new(12, 12);
+    let head = harness.snake(0, 1, 6);
+    harness.play(&Script::new(), 3);
+    harness.world.write::<Extension>().remove(head);
+    harness.play(&Script::new(), 1);
+    assert_eq!(harness.body(head), vec![(1, 2), (1, 3), (1, 4), (1, 5)]);
+
+    // Heads move against the board as it was before the tick, so the end of the tail is still in
+    // the tile it's about to leave when a snake of four going around a square gets there.
+    let script = Script::new()
+        .steer(5, 0, RIGHT)
+        .steer(6, 0, DOWN)
+        .steer(7, 0, LEFT);
+    harness.play(&script, 2);
+    assert!(harness.deaths().is_empty());
+    assert_eq!(harness.body(head), vec![(2, 3), (2, 2), (1, 2), (1, 3)]);
+
+    harness.play(&script, 1);
+    assert_eq!(harness.deaths(), vec![(head, Cause::Hit(Type::Snake))]);
+    assert!(harness.game_over());
+}
+
+#[test]
+fn snakes_running_into_each_other_die() {
+    let mut harness = Harness::new(12, 12);
+    let left = harness.snake(0, 2, 10);
+    let right = harness.snake(1, 6, 10);
+
+    // The left snake turns into the side of the right one while it's still growing.
+    let script = Script::new().steer(3, 0, RIGHT);
+    harness.play(&script, 5);
+    assert!(harness.deaths().is_empty());
+
+    harness.play(&script, 1);
+    assert_eq!(harness.tile(right), Some((6, 4)));
+    assert_eq!(harness.deaths(), vec![(left, Cause::Hit(Type::Snake))]);
+    assert!(harness.alive(right));
+    assert!(harness.game_over());
+}
+
+#[test]
+fn eating_food_grows_the_snake_and_scores() {
+    let mut harness = Harness::new(12, 12);
+    let head = harness.snake(0, 5, 10);
+    let food = harness.food(5, 2);
+
+    harness.play(&Script::new(), 7);
+    assert_eq!(harness.length(head), 7);
+    assert_eq!(harness.score(head), 0);
+
+    harness.tick();
+    assert_eq!(harness.tile(head), Some((5, 2)));
+    assert_eq!(harness.score(head), 1);
+    assert!(!harness.alive(food));
+    assert!(harness.deaths().is_empty());
+
+    // The snake grows by a piece a tick, starting the same tick it eats.
+    assert_eq!(harness.length(head), 8);
+    harness.play(&Script::new().steer(9, 0, LEFT), GROWTH as u64 - 1);
+    assert_eq!(harness.length(head), 7 + GROWTH as u32);
+    harness.tick();
+    assert_eq!(harness.length(head), 7 + GROWTH as u32);
+}
+
+#[test]
+fn heads_meeting_in_a_tile_both_die() {
+    let mut harness = Harness::new(12, 12);
+    let first = harness.snake(0, 4, 9);
+    let second = harness.snake(1, 6, 9);
+    let food = harness.food(5, 8);
+
+    // Whichever snake is moved first, neither gets there first.
+    let script = Script::new()
+        .steer(2, 0, RIGHT)
+        .steer(2, 1, LEFT);
+    harness.play(&script, 2);
+
+    let mut deaths = harness.deaths();
+    deaths.sort_by_key(|&(who, _)| who.id());
+    assert_eq!(deaths, vec![(first, Cause::Hit(Type::Snake)), (second, Cause::Hit(Type::Snake))]);
+    assert_eq!(harness.score(first) + harness.score(second), 0);
+    assert!(harness.alive(food));
+    assert_eq!(harness.at(5, 8), Some(Type::Food));
+}
+
+#[test]
+fn nothing_moves_once_the_game_is_over() {
+    let mut harness = Harness::new(12, 12);
+    let head = harness.snake(0, 5, 1);
+
+    harness.play(&Script::new(), 2);
+    assert!(harness.game_over());
+
+    let body = harness.body(head);
+    harness.play(&Script::new(), 3);
+    assert_eq!(harness.body(head), body);
+}
+
+#[test]
+fn no_event_is_lost_in_a_busy_tick() {
+    // More snakes than the event queue keeps from older ticks, all running off the top at once.
+    let count = CAPACITY + 100;
+    let mut harness = Harness::new(count, 3);
+    let heads = (0..count).map(|x| harness.snake(x, x as i32, 0)).collect::<Vec<_>>();
+
+    harness.tick();
+    let deaths = harness.deaths();
+    assert_eq!(deaths.len(), count);
+    assert!(deaths.contains(&(heads[0], Cause::Edge)));
+    assert_eq!(deaths.last(), Some(&(heads[count - 1], Cause::Edge)));
+}
+
+// Where the food is in a simulation.
+fn foods(simulation: &Simulation) -> Vec<(Entity, Tile)> {
+    let types = simulation.world.read::<Type>();
+    let tiles = simulation.world.read::<Tile>();
+    (&*simulation.world.entities(), &types, &tiles).join()
+        .filter(|&(_, kind, _)| *kind == Type::Food)
+        .map(|(entity, _, tile)| (entity, tile.clone()))
+        .collect()
+}
+
+// The tile in front of the head of the only snake in a simulation, which heads up at the start.
+fn ahead(simulation: &Simulation) -> Tile {
+    let head = (&simulation.world.read::<Snake>(), &simulation.world.read::<Tile>()).join()
+        .map(|(_, tile)| tile.clone())
+        .next()
+        .unwrap();
+    Tile { x: head.x, y: head.y - 1 }
+}
+
+#[test]
+fn food_placed_when_eaten_is_there_from_the_start() {
+    let mut simulation = Simulation::seeded(12, 12, 1, SEED);
+    *simulation.world.write_resource::<SpawnRules>() = SpawnRules {
+        when: When::OnEat,
+        expiring: 0,
+        live: 0,
+        poison: 0,
+        .. SpawnRules::default()
+    };
+
+    simulation.step();
+    let placed = foods(&simulation);
+    assert_eq!(placed.len(), 1);
+
+    // Put the food in front of the snake, which eats it on the next tick for another to take its
+    // place.
+    let (food, from) = placed[0].clone();
+    let ahead = ahead(&simulation);
+    {
+        let mut grid = simulation.world.write_resource::<Grid>();
+        grid.remove(from.x as usize, from.y as usize, food);
+        grid.insert(ahead.x as usize, ahead.y as usize, food, Type::Food);
+    }
+    simulation.world.write::<Tile>().insert(food, ahead);
+
+    simulation.step();
+    let replaced = foods(&simulation);
+    assert_eq!(replaced.len(), 1);
+    assert!(replaced[0].0 != food);
+
+    let score = simulation.world.read::<Snake>().join().map(|snake| snake.score).next();
+    assert_eq!(score, Some(1));
+}
+
+#[test]
+fn food_eaten_as_it_runs_out_is_only_swallowed() {
+    let mut simulation = Simulation::seeded(12, 12, 1, SEED);
+    simulation.step();
+
+    // Food in front of the snake with a tick left, eaten on the tick it would have expired.
+    let ahead = ahead(&simulation);
+    let food = simulation.world.create_entity()
+        .with(ahead.clone())
+        .with(Type::Food)
+        .with(Expiry { remaining: 1 })
+        .build();
+    simulation.world.write_resource::<Grid>().insert(ahead.x as usize, ahead.y as usize, food, Type::Food);
+
+    let mut reader = simulation.world.read_resource::<Events>().reader();
+    simulation.step();
+    let events = simulation.world.read_resource::<Events>().read(&mut reader);
+
+    let swallowed = events.iter().any(|event| match *event {
+        GameEvent::FoodSwallowed { food: swallowed, .. } => swallowed == food,
+        _ => false,
+    });
+    let expired = events.iter().any(|event| match *event {
+        GameEvent::FoodExpired { food: expired, .. } => expired == food,
+        _ => false,
+    });
+    assert!(swallowed);
+    assert!(!expired);
+}
@@ -0,0 +1,91 @@
+
+// Games captured in a snapshot and restored into another simulation, which should carry on exactly
+// as the one it was taken from.
+
+extern crate snake;
+extern crate specs;
+
+use specs::Join;
+
+use snake::components::{Snake, Tick};
+use snake::level::Level;
+use snake::mode::GameMode;
+use snake::simulation::Simulation;
+
+const SEED: u64 = 3;
+
+const UP: (i8, i8) = (0, -1);
+const LEFT: (i8, i8) = (-1, 0);
+const RIGHT: (i8, i8) = (1, 0);
+
+// A simulation with nothing in common with the ones snapshots are taken from, to restore into.
+fn elsewhere() -> Simulation {
+    Simulation::seeded(20, 20, 1, SEED + 1)
+}
+
+fn snakes(simulation: &Simulation) -> usize {
+    simulation.world.read::<Snake>().join().count()
+}
+
+#[test]
+fn restoring_a_snapshot_gives_the_same_game() {
+    let mut original = Simulation::seeded(12, 12, 2, SEED);
+    original.level(Level::named("pillars").unwrap());
+    for _ in 0..20 {
+        original.step();
+    }
+    original.world.write_resource::<Tick>().slowed = true;
+
+    let snapshot = original.snapshot();
+    let mut restored = elsewhere();
+    restored.restore(&snapshot);
+
+    assert_eq!(restored.players, 2);
+    assert_eq!(restored.checksum(), original.checksum());
+    assert_eq!(restored.snapshot(), snapshot);
+    assert_eq!(*restored.world.read_resource::<Level>(), *original.world.read_resource::<Level>());
+    assert!(restored.world.read_resource::<Tick>().slowed);
+}
+
+#[test]
+fn restored_games_play_on_in_lockstep() {
+    let mut original = Simulation::seeded(12, 12, 2, SEED);
+    original.mode(GameMode::Survival);
+    original.step_with(&[(0, LEFT), (1, RIGHT)]);
+    original.step_with(&[(0, UP), (1, UP)]);
+
+    let mut restored = elsewhere();
+    restored.restore(&original.snapshot());
+
+    // Food, decay and the arena all come from the saved random numbers and settings.
+    let turns = [UP, RIGHT, UP, LEFT];
+    for tick in 0..200 {
+        let inputs = [(0, turns[tick / 3 % 4]), (1, turns[(tick / 3 + 2) % 4])];
+        original.step_with(&inputs);
+        restored.step_with(&inputs);
+        assert_eq!(restored.checksum(), original.checksum(), "apart after {} ticks", tick + 1);
+    }
+    assert_eq!(restored.snapshot(), original.snapshot());
+}
+
+#[test]
+fn every_player_comes_back_after_a_restart() {
+    let mut original = Simulation::seeded(12, 12, 3, SEED);
+    original.mode(GameMode::BattleRoyale);
+
+    // The first snake runs off the side of the board and is taken off it.
+    original.step();
+    for _ in 0..3 {
+        original.step_with(&[(0, LEFT)]);
+    }
+    assert!(!original.game_over());
+    assert_eq!(snakes(&original), 2);
+
+    let mut restored = elsewhere();
+    restored.restore(&original.snapshot());
+    assert_eq!(restored.players, 3);
+    assert_eq!(snakes(&restored), 2);
+
+    restored.reset();
+    assert_eq!(snakes(&restored), 3);
+}
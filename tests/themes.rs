@@ -0,0 +1,36 @@
+
+// The themes in `resources/themes`, which have to tell everything on the board apart.
+
+extern crate snake;
+
+use snake::theme::{Colour, Theme};
+
+// How far apart two colours on the board have to be in at least one of red, green or blue to be told
+// apart at a glance.
+const MIN_DIFFERENCE: f32 = 0.15;
+
+fn difference(a: Colour, b: Colour) -> f32 {
+    (0..3).map(|channel| (a[channel] - b[channel]).abs()).fold(0.0, f32::max)
+}
+
+#[test]
+fn everything_on_the_board_has_a_colour_of_its_own() {
+    let themes = Theme::load_all(format!("{}/resources/themes", env!("CARGO_MANIFEST_DIR")));
+    assert!(!themes.is_empty());
+
+    for theme in themes {
+        // The board and its lines are under everything, and the HUD is drawn off to the side.
+        let under = ["board", "grid_lines", "hud"];
+        let colours = theme.textures().into_iter()
+            .filter(|&(ref name, _)| !under.contains(&name.rsplit('/').next().unwrap()))
+            .collect::<Vec<_>>();
+
+        for (index, &(ref name, colour)) in colours.iter().enumerate() {
+            let close = colours[index + 1..].iter()
+                .find(|&&(_, other)| difference(colour, other) < MIN_DIFFERENCE);
+            if let Some(&(ref other, other_colour)) = close {
+                panic!("{} {:?} and {} {:?} are too close to tell apart", name, colour, other, other_colour);
+            }
+        }
+    }
+}